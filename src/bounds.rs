@@ -0,0 +1,117 @@
+use crate::model_data::ModelData;
+use crate::primitive::Primitive;
+use std::collections::HashMap;
+
+/// Axis-aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: [f32; 3],
+    pub radius: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub aabb: BoundingBox,
+    /// Centered on the box, not the smallest sphere but close enough for culling
+    pub sphere: BoundingSphere,
+}
+
+impl BoundingBox {
+    pub fn new(point: [f32; 3]) -> BoundingBox {
+        BoundingBox {
+            min: point,
+            max: point,
+        }
+    }
+
+    pub fn extend(&mut self, point: [f32; 3]) {
+        for (i, c) in point.iter().enumerate() {
+            self.min[i] = self.min[i].min(*c);
+            self.max[i] = self.max[i].max(*c);
+        }
+    }
+
+    pub fn center(&self) -> [f32; 3] {
+        [
+            (self.min[0] + self.max[0]) / 2.0,
+            (self.min[1] + self.max[1]) / 2.0,
+            (self.min[2] + self.max[2]) / 2.0,
+        ]
+    }
+}
+
+impl Bounds {
+    /// Bounds of the given points, non-finite points are ignored.
+    /// Returns None if no point is left.
+    pub fn from_points(points: &[[f32; 3]]) -> Option<Bounds> {
+        let finite: Vec<[f32; 3]> = points
+            .iter()
+            .filter(|p| p.iter().all(|c| c.is_finite()))
+            .cloned()
+            .collect();
+        let mut aabb = BoundingBox::new(*finite.first()?);
+        for p in &finite {
+            aabb.extend(*p);
+        }
+        let center = aabb.center();
+        let radius = finite
+            .iter()
+            .map(|p| distance(center, *p))
+            .fold(0.0, f32::max);
+        Some(Bounds {
+            aabb,
+            sphere: BoundingSphere { center, radius },
+        })
+    }
+}
+
+impl<'a> Primitive<'a> {
+    /// Bounds of the geometry vertices in `index_range`, in model space
+    pub fn bounds(&self) -> Option<Bounds> {
+        let points: Vec<[f32; 3]> = self
+            .geometry_vertices()
+            .iter()
+            .map(|v| {
+                let p = &v.position_coordinates;
+                [p.x, p.y, p.z]
+            })
+            .collect();
+        Bounds::from_points(&points)
+    }
+}
+
+impl ModelData {
+    /// Bounds of the vertices influenced by each joint, by in-vertex ID (see `Joint::in_vertex_id`).
+    /// Influences with a weight below `min_weight` are ignored, positions are in bind pose model space.
+    pub fn joint_bounds(&self, min_weight: f32) -> HashMap<u16, Bounds> {
+        let mut points: HashMap<u16, Vec<[f32; 3]>> = HashMap::new();
+        for group in &self.groups {
+            for primitive in group.primitives() {
+                let geometry = primitive.geometry_vertices();
+                let rigging = primitive.rigging_vertices();
+                for (g, r) in geometry.iter().zip(rigging) {
+                    let p = &g.position_coordinates;
+                    for (joint, weight) in r.influences() {
+                        if weight >= min_weight {
+                            points.entry(joint).or_default().push([p.x, p.y, p.z]);
+                        }
+                    }
+                }
+            }
+        }
+        points
+            .into_iter()
+            .filter_map(|(joint, p)| Bounds::from_points(&p).map(|b| (joint, b)))
+            .collect()
+    }
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
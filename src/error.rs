@@ -31,7 +31,11 @@ pub enum ISM2ImportError {
     MagicNumber(WrongMagicNumber),
     UnknownSubSection(UnknownSubSection),
     NoAttributes,
+    NoStringTable,
     UnrecognizedBufferType,
+    InvalidParentOffset(u32),
+    JointCycle(String),
+    InvalidVertexAttribute(u32),
 }
 
 impl Error for ISM2ImportError {
@@ -41,9 +45,19 @@ impl Error for ISM2ImportError {
             ISM2ImportError::MagicNumber(ref e) => e.description(),
             ISM2ImportError::UnknownSubSection(ref e) => e.description(),
             ISM2ImportError::NoAttributes => "No Attribute was specified for a Vertex Buffer",
+            ISM2ImportError::NoStringTable => {
+                "The first section of the file is not a Strings Table"
+            }
             ISM2ImportError::UnrecognizedBufferType => {
                 "Impossible to infer what type of buffer to read in Joint Extra"
             }
+            ISM2ImportError::InvalidParentOffset(_) => {
+                "The parent offset of a joint does not point to a joint"
+            }
+            ISM2ImportError::JointCycle(_) => "A joint is its own ancestor",
+            ISM2ImportError::InvalidVertexAttribute(_) => {
+                "A vertex attribute cannot be read with the others"
+            }
         }
     }
 }
@@ -55,9 +69,23 @@ impl fmt::Display for ISM2ImportError {
             ISM2ImportError::MagicNumber(ref e) => e.fmt(f),
             ISM2ImportError::UnknownSubSection(ref e) => e.fmt(f),
             ISM2ImportError::NoAttributes => write!(f, "No Attributes in Vertices Buffer"),
+            ISM2ImportError::NoStringTable => write!(f, "No Strings Table in file"),
             ISM2ImportError::UnrecognizedBufferType => {
                 write!(f, "Impossible to infer type of buffer")
             }
+            ISM2ImportError::InvalidParentOffset(o) => {
+                write!(f, "No joint at parent offset 0x{:X}", o)
+            }
+            ISM2ImportError::JointCycle(ref name) => {
+                write!(f, "Joint '{}' is part of a parent cycle", name)
+            }
+            ISM2ImportError::InvalidVertexAttribute(t) => {
+                write!(
+                    f,
+                    "Vertex attribute 0x{:X} cannot be read with the others",
+                    t
+                )
+            }
         }
     }
 }
@@ -76,3 +104,80 @@ impl From<MagicNumberCheckError> for ISM2ImportError {
         }
     }
 }
+
+#[derive(Debug)]
+pub enum SkeletonEditError {
+    NoSkeleton,
+    NoSuchJoint(usize),
+    DuplicateName(String),
+    WouldCreateCycle,
+    UnboundInfluences(String),
+    NoSuchBinding(usize),
+}
+
+impl Error for SkeletonEditError {
+    fn description(&self) -> &str {
+        match *self {
+            SkeletonEditError::NoSkeleton => "The file has no Joint Definition section",
+            SkeletonEditError::NoSuchJoint(_) => "Joint index out of range",
+            SkeletonEditError::DuplicateName(_) => "Another joint already has this name",
+            SkeletonEditError::WouldCreateCycle => "A joint cannot become its own ancestor",
+            SkeletonEditError::UnboundInfluences(_) => {
+                "Vertices are bound to a root joint that is being removed"
+            }
+            SkeletonEditError::NoSuchBinding(_) => "Skin binding index out of range",
+        }
+    }
+}
+
+impl fmt::Display for SkeletonEditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SkeletonEditError::NoSkeleton => write!(f, "No skeleton in file"),
+            SkeletonEditError::NoSuchJoint(i) => write!(f, "No joint at index {}", i),
+            SkeletonEditError::DuplicateName(ref name) => {
+                write!(f, "A joint named '{}' already exists", name)
+            }
+            SkeletonEditError::WouldCreateCycle => write!(f, "Joint would become its own ancestor"),
+            SkeletonEditError::UnboundInfluences(ref name) => {
+                write!(f, "Root joint '{}' still has vertices bound to it", name)
+            }
+            SkeletonEditError::NoSuchBinding(i) => write!(f, "No skin binding at index {}", i),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    IO(IOError),
+    Texture(String, String),
+    GlTF(String),
+}
+
+impl Error for ExportError {
+    fn description(&self) -> &str {
+        match *self {
+            ExportError::IO(ref e) => e.description(),
+            ExportError::Texture(_, _) => "A texture could not be loaded",
+            ExportError::GlTF(_) => "The glTF file could not be written",
+        }
+    }
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::IO(ref e) => e.fmt(f),
+            ExportError::Texture(ref name, ref e) => {
+                write!(f, "Failed to load texture '{}': {}", name, e)
+            }
+            ExportError::GlTF(ref e) => write!(f, "Failed to write glTF: {}", e),
+        }
+    }
+}
+
+impl From<IOError> for ExportError {
+    fn from(e: IOError) -> ExportError {
+        ExportError::IO(e)
+    }
+}
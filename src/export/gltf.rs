@@ -0,0 +1,876 @@
+use crate::bounds::Bounds;
+use crate::error::ExportError;
+use crate::model_data::{SubSection, Vertex, VerticesDataBuffer};
+use crate::skeleton::Skeleton;
+use crate::{Section, ISM2};
+use ez_io::WriteE;
+use my_gltf::{
+    accessors::Accessor, asset::Asset, buffer_views::BufferView, buffers::Buffer, images::Image,
+    materials::BaseColorTexture, materials::Material, materials::PbrMetallicRoughness,
+    meshes::Mesh, meshes::Primitive, nodes::Node, scenes::Scene, skins::Skin, textures::Texture,
+    GlTF,
+};
+use png::{Encoder, HasParameters};
+use rgb::ComponentBytes;
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, File};
+use std::io::ErrorKind as IOErrorKind;
+use std::io::{BufReader, Write};
+use std::path::PathBuf;
+use tid::TID;
+
+type Result<T> = std::result::Result<T, ExportError>;
+
+#[derive(Default)]
+pub struct Options {
+    /// Loads the textures of Texture Definition sections, no material is created without it
+    pub texture_loader: Option<Box<dyn TextureLoader>>,
+}
+
+/// RGBA image, 8 bits per channel
+pub struct TextureImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+pub trait TextureLoader {
+    /// Loads a texture from its name without extension, `None` if there is no such texture
+    fn load(&self, name: &str) -> Result<Option<TextureImage>>;
+}
+
+/// Loads `.tid` files from a folder, usually `texture/001` next to the ISM2 file
+pub struct TidLoader {
+    pub directory: PathBuf,
+}
+
+/// Where the glTF file and the files it refers to are written
+pub trait OutputSink {
+    fn write(&mut self, name: &str, data: &[u8]) -> Result<()>;
+}
+
+/// Writes every file in a folder, creating it if needed
+pub struct DirectorySink {
+    pub directory: PathBuf,
+}
+
+/// Keeps every file in memory
+#[derive(Default)]
+pub struct MemorySink {
+    pub files: BTreeMap<String, Vec<u8>>,
+}
+
+pub struct GltfDocument {
+    pub gltf: GlTF,
+    /// Buffers and images referred to by URI in `gltf`, by name
+    pub files: Vec<(String, Vec<u8>)>,
+    /// Data that was left out while converting
+    pub warnings: Vec<String>,
+}
+
+struct IVertexRig {
+    /// Joint in-vertex ID and weight
+    influences: Vec<(u16, f32)>,
+    nb_influences: usize,
+}
+
+struct IMesh {
+    vertices: Vec<Vertex>,
+    vertices_rig: Vec<IVertexRig>,
+    faces: Vec<(u32, u32, u32)>,
+    group: usize,
+    bounds: Option<Bounds>,
+}
+
+struct INode {
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    scale: [f32; 3],
+    name: String,
+    children: Vec<usize>,
+}
+
+/// Buffers of the glTF file, each stored in its own file
+#[derive(Default)]
+struct IBuffers {
+    buffers: Vec<Buffer>,
+    buffer_views: Vec<BufferView>,
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl TextureLoader for TidLoader {
+    fn load(&self, name: &str) -> Result<Option<TextureImage>> {
+        let file = match File::open(self.directory.join(format!("{}.tid", name))) {
+            Ok(f) => f,
+            Err(ref e) if e.kind() == IOErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(ExportError::IO(e)),
+        };
+        let reader = &mut BufReader::new(file);
+        let tid = TID::import(reader)
+            .map_err(|e| ExportError::Texture(name.to_owned(), format!("{:?}", e)))?;
+        let image = tid
+            .convert(reader)
+            .map_err(|e| ExportError::Texture(name.to_owned(), format!("{:?}", e)))?;
+        Ok(Some(TextureImage {
+            width: tid.dimensions.width,
+            height: tid.dimensions.height,
+            pixels: image.as_bytes().to_vec(),
+        }))
+    }
+}
+
+impl OutputSink for DirectorySink {
+    fn write(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        create_dir_all(&self.directory)?;
+        File::create(self.directory.join(name))?.write_all(data)?;
+        Ok(())
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn write(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        self.files.insert(name.to_owned(), data.to_vec());
+        Ok(())
+    }
+}
+
+impl GltfDocument {
+    /// Writes the glTF file as `name`, followed by the files it refers to
+    pub fn write<S: OutputSink + ?Sized>(&self, sink: &mut S, name: &str) -> Result<()> {
+        let mut gltf_out = Vec::new();
+        self.gltf
+            .write_gltf_pretty(&mut gltf_out)
+            .map_err(|e| ExportError::GlTF(format!("{:?}", e)))?;
+        sink.write(name, &gltf_out)?;
+        for (file_name, data) in &self.files {
+            sink.write(file_name, data)?;
+        }
+        Ok(())
+    }
+}
+
+impl IBuffers {
+    /// Adds a buffer along with a view covering all of it, returns the ID of the view
+    fn push(&mut self, uri: String, data: Vec<u8>) -> usize {
+        let buffer_id = self.buffers.len();
+        self.buffers.push(Buffer {
+            byte_length: data.len(),
+            uri: Some(uri.clone()),
+        });
+        let buffer_view_id = self.buffer_views.len();
+        self.buffer_views.push(BufferView {
+            buffer: buffer_id,
+            byte_offset: None,
+            byte_length: data.len(),
+            byte_stride: None,
+        });
+        self.files.push((uri, data));
+        buffer_view_id
+    }
+}
+
+/// Converts the geometry, skeleton and textures of a file.
+/// Every mesh gets its own buffers, meshes of a Model Data group are children of a node for the group.
+pub fn to_gltf(ism: &ISM2, options: &Options) -> Result<GltfDocument> {
+    // Create variables for defining what we want
+    let mut warnings = Vec::new();
+    let mut i_meshes: Vec<IMesh> = Vec::new();
+    let mut i_nodes: Vec<INode> = Vec::new();
+    let mut i_joints: Vec<[f32; 16]> = Vec::new();
+    let mut i_textures: Vec<String> = Vec::new();
+    let mut i_joint_extra = None;
+    let mut nb_groups = 0usize;
+
+    // Get the required information from the ISM file
+    let skeleton = ism.skeleton();
+    for section in &ism.sections {
+        match section {
+            Section::ModelData(model_data) => {
+                for group in &model_data.groups {
+                    for sub_section in &group.sub_sections {
+                        if let SubSection::Vertices(vertices) = sub_section {
+                            if let VerticesDataBuffer::Unknown08(unknown) = &vertices.buffer {
+                                warnings.push(format!(
+                                    "Ignored a 0x08 attribute of {} vertices in Model Data",
+                                    unknown.values.len()
+                                ));
+                            }
+                        }
+                    }
+                    for primitive in group.primitives() {
+                        let i_vertices = primitive.vertices();
+                        let degenerate = i_vertices.iter().filter(|v| v.degenerate_normal).count();
+                        if degenerate > 0 {
+                            warnings.push(format!(
+                                "{} vertices of mesh {} have a wrong normal",
+                                degenerate,
+                                i_meshes.len()
+                            ));
+                        }
+                        let mut i_vertices_rig = Vec::new();
+                        for vertex in primitive.rigging_vertices() {
+                            i_vertices_rig.push(IVertexRig {
+                                influences: vertex.influences().collect(),
+                                nb_influences: vertex.nb_influences,
+                            });
+                        }
+                        i_meshes.push(IMesh {
+                            vertices: i_vertices,
+                            vertices_rig: i_vertices_rig,
+                            faces: primitive.faces(),
+                            group: nb_groups,
+                            bounds: primitive.bounds(),
+                        });
+                    }
+                    nb_groups += 1;
+                }
+            }
+            Section::JointDefinition(_) => {
+                // Handled through the skeleton below
+            }
+            Section::JointExtra(je) => {
+                i_joint_extra = Some(je);
+            }
+            Section::TextureDefinition(td) => {
+                for texture in &td.sub_sections {
+                    let test: Vec<&str> = texture.original_name.split('.').collect();
+                    i_textures.push(test[0].to_string());
+                }
+            }
+        }
+    }
+
+    if i_meshes.is_empty() {
+        warnings.push("No geometry in file, no mesh is converted".to_owned());
+    }
+    if skeleton.is_none() && i_meshes.iter().any(|m| !m.vertices_rig.is_empty()) {
+        warnings.push("No skeleton in file, rigging is left out".to_owned());
+    }
+
+    // Convert joints to nodes
+    if let Some(skeleton) = &skeleton {
+        for (id, pose) in skeleton.bind_pose().into_iter().enumerate() {
+            let joint = skeleton.joints()[id];
+            i_nodes.push(INode {
+                translation: pose.translation,
+                rotation: [
+                    pose.rotation.x,
+                    pose.rotation.y,
+                    pose.rotation.z,
+                    pose.rotation.w,
+                ],
+                scale: pose.scale,
+                name: joint.name.clone(),
+                children: skeleton.children(id).to_vec(),
+            });
+        }
+        if let Some(je) = i_joint_extra {
+            // Rebuilt from the hierarchy for joints missing from Joint Extra
+            let computed = skeleton.inverse_bind_matrices();
+            for (id, matrix) in skeleton
+                .inverse_bind_matrices_from(je)
+                .into_iter()
+                .enumerate()
+            {
+                i_joints.push(matrix.unwrap_or(computed[id]));
+            }
+        } else if i_meshes.iter().any(|m| !m.vertices_rig.is_empty()) {
+            warnings.push(
+                "No Joint Extra in file, inverse bind matrices are computed from the skeleton"
+                    .to_owned(),
+            );
+            i_joints = skeleton.inverse_bind_matrices();
+        }
+    }
+
+    // Create glTF sections
+    let mut i_buffers = IBuffers::default();
+    let mut nodes = Vec::new();
+    let mut accessors = Vec::new();
+    let mut meshes = Vec::new();
+    let mut materials = Vec::new();
+    let mut skins = None;
+    let mut images = Vec::new();
+    let mut textures = Vec::new();
+
+    // Textures, converted to PNG
+    if let Some(loader) = &options.texture_loader {
+        for tex_name in i_textures {
+            let image = match loader.load(&tex_name)? {
+                Some(i) => i,
+                None => {
+                    warnings.push(format!(
+                        "Failed to open texture '{}', ignoring...",
+                        tex_name
+                    ));
+                    continue;
+                }
+            };
+            let mut png_out = Vec::new();
+            {
+                let mut encoder = Encoder::new(&mut png_out, image.width, image.height);
+                encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+                let mut writer = encoder
+                    .write_header()
+                    .map_err(|e| ExportError::Texture(tex_name.clone(), e.to_string()))?;
+                writer
+                    .write_image_data(&image.pixels)
+                    .map_err(|e| ExportError::Texture(tex_name.clone(), e.to_string()))?;
+            }
+            let uri = format!("{}.png", tex_name);
+            i_buffers.files.push((uri.clone(), png_out));
+            let image_id = images.len();
+            images.push(Image {
+                uri: Some(uri),
+                buffer_view: None,
+            });
+            let texture_id = textures.len();
+            textures.push(Texture {
+                source: Some(image_id),
+                sampler: None,
+            });
+            materials.push(Material {
+                pbr_metallic_roughness: Some(PbrMetallicRoughness {
+                    base_color_texture: Some(BaseColorTexture {
+                        index: Some(texture_id),
+                    }),
+                    metallic_factor: Some(0f64),
+                    roughness_factor: Some(1f64),
+                }),
+            });
+        }
+    }
+
+    // Joints Inverse Bind Matrices if necessary
+    let joints_inv_accessor_id = if !i_joints.is_empty() {
+        let mut data = Vec::new();
+        for matrix in &i_joints {
+            // Matrices are row-major, glTF wants them column-major
+            for id in &[0usize, 4, 8, 12, 1, 5, 9, 13, 2, 6, 10, 14, 3, 7, 11, 15] {
+                data.write_le_to_f32(matrix[*id])?;
+            }
+        }
+        let buffer_view_id = i_buffers.push("joints_inv.bin".to_owned(), data);
+        accessors.push(Accessor {
+            buffer_view: Some(buffer_view_id),
+            component_type: 5126,
+            count: i_joints.len(),
+            attribute_type: "MAT4".to_owned(),
+            min: None,
+            max: None,
+        });
+        Some(accessors.len() - 1)
+    } else {
+        None
+    };
+
+    // Joints in ISM2 file as nodes
+    let start = nodes.len();
+    let mut root_node_id = None;
+    for (i, i_node) in i_nodes.into_iter().enumerate() {
+        let ch = if !i_node.children.is_empty() {
+            Some(i_node.children.iter().map(|id| id + start).collect())
+        } else {
+            None
+        };
+        if i_node.name == "root" {
+            root_node_id = Some(i)
+        }
+        nodes.push(Node {
+            mesh: None,
+            translation: Some(i_node.translation),
+            name: Some(i_node.name),
+            rotation: Some(i_node.rotation),
+            scale: Some(i_node.scale),
+            children: ch,
+            skin: None,
+        });
+    }
+
+    // Create the skin
+    if let Some(id) = joints_inv_accessor_id {
+        skins = Some(vec![Skin {
+            inverse_bind_matrices: Some(id),
+            skeleton: root_node_id,
+            name: None,
+            joints: (0..nodes.len()).collect(),
+        }]);
+    }
+
+    // Meshes
+    let mut group_nodes = vec![Vec::new(); nb_groups];
+    let mut nb_unknown_joints = 0usize;
+    for (mesh_counter, i_mesh) in i_meshes.into_iter().enumerate() {
+        // Indices only need 32 bits for meshes with more vertices than a u16 can index
+        let wide_indices = i_mesh.vertices.len() > usize::from(u16::MAX) + 1;
+        let mut data = Vec::new();
+        for i_face in &i_mesh.faces {
+            for &index in &[i_face.0, i_face.1, i_face.2] {
+                if wide_indices {
+                    data.write_le_to_u32(index)?;
+                } else {
+                    data.write_le_to_u16(index as u16)?;
+                }
+            }
+        }
+        let buffer_view_id = i_buffers.push(format!("mesh_{}.bin", mesh_counter), data);
+        let accessor_id = accessors.len();
+        accessors.push(Accessor {
+            buffer_view: Some(buffer_view_id),
+            component_type: if wide_indices { 5125 } else { 5123 },
+            count: i_mesh.faces.len() * 3,
+            attribute_type: "SCALAR".to_owned(),
+            max: None,
+            min: None,
+        });
+
+        // Vertices used by this mesh only
+        let mut positions = Vec::new();
+        let mut uv_maps = Vec::new();
+        let mut normals = Vec::new();
+        for i_vertex in &i_mesh.vertices {
+            for component in &i_vertex.position {
+                positions.write_le_to_f32(*component)?;
+            }
+            for component in &i_vertex.texture_coordinates {
+                uv_maps.write_le_to_f32(*component)?;
+            }
+            for component in &i_vertex.normal {
+                normals.write_le_to_f32(*component)?;
+            }
+            // Tangents are not written as I cannot confirm if the data read from the file are actually Tangents
+        }
+        let count = i_mesh.vertices.len();
+        let buffer_view_id =
+            i_buffers.push(format!("mesh_{}_positions.bin", mesh_counter), positions);
+        let positions_accessor_id = accessors.len();
+        accessors.push(Accessor {
+            buffer_view: Some(buffer_view_id),
+            component_type: 5126,
+            count,
+            attribute_type: "VEC3".to_owned(),
+            min: i_mesh.bounds.map(|b| b.aabb.min.to_vec()),
+            max: i_mesh.bounds.map(|b| b.aabb.max.to_vec()),
+        });
+        let buffer_view_id = i_buffers.push(format!("mesh_{}_uv_maps.bin", mesh_counter), uv_maps);
+        let uv_maps_accessor_id = accessors.len();
+        accessors.push(Accessor {
+            buffer_view: Some(buffer_view_id),
+            component_type: 5126,
+            count,
+            attribute_type: "VEC2".to_owned(),
+            min: None,
+            max: None,
+        });
+        let buffer_view_id = i_buffers.push(format!("mesh_{}_normals.bin", mesh_counter), normals);
+        let normals_accessor_id = accessors.len();
+        accessors.push(Accessor {
+            buffer_view: Some(buffer_view_id),
+            component_type: 5126,
+            count,
+            attribute_type: "VEC3".to_owned(),
+            min: None,
+            max: None,
+        });
+
+        // Extra UV sets and colors if every vertex has them
+        let nb_extra_uv_sets = i_mesh
+            .vertices
+            .iter()
+            .map(|v| v.extra_texture_coordinates.len())
+            .min()
+            .unwrap_or(0);
+        let mut extra_uv_maps_accessor_ids = Vec::new();
+        for set in 0..nb_extra_uv_sets {
+            let mut uv_maps = Vec::new();
+            for i_vertex in &i_mesh.vertices {
+                let uv = &i_vertex.extra_texture_coordinates[set];
+                uv_maps.write_le_to_f32(uv[0])?;
+                uv_maps.write_le_to_f32(uv[1])?;
+            }
+            let buffer_view_id = i_buffers.push(
+                format!("mesh_{}_uv_maps_{}.bin", mesh_counter, set + 1),
+                uv_maps,
+            );
+            extra_uv_maps_accessor_ids.push(accessors.len());
+            accessors.push(Accessor {
+                buffer_view: Some(buffer_view_id),
+                component_type: 5126,
+                count,
+                attribute_type: "VEC2".to_owned(),
+                min: None,
+                max: None,
+            });
+        }
+        let mut colors_accessor_id = None;
+        if count > 0 && i_mesh.vertices.iter().all(|v| v.color.is_some()) {
+            let mut colors = Vec::new();
+            for color in i_mesh.vertices.iter().filter_map(|v| v.color) {
+                for component in &color {
+                    colors.write_le_to_f32(*component)?;
+                }
+            }
+            let buffer_view_id =
+                i_buffers.push(format!("mesh_{}_colors.bin", mesh_counter), colors);
+            colors_accessor_id = Some(accessors.len());
+            accessors.push(Accessor {
+                buffer_view: Some(buffer_view_id),
+                component_type: 5126,
+                count,
+                attribute_type: "VEC4".to_owned(),
+                min: None,
+                max: None,
+            });
+        }
+
+        // Joints and Weights if necessary, as sets of 4 influences
+        let mut skin_accessor_ids = Vec::new();
+        let rigged = !i_mesh.vertices_rig.is_empty() && i_mesh.vertices_rig.len() == count;
+        if let Some(skeleton) = skeleton.as_ref().filter(|_| rigged) {
+            let nb_sets = if i_mesh.vertices_rig.iter().any(|r| r.nb_influences > 4) {
+                2
+            } else {
+                1
+            };
+            for set in 0..nb_sets {
+                let mut joints = Vec::new();
+                let mut weights = Vec::new();
+                for i_vertex_rig in &i_mesh.vertices_rig {
+                    let mut influences = i_vertex_rig.influences.iter().skip(set * 4);
+                    for _ in 0..4 {
+                        let (joint, weight) = match influences.next() {
+                            Some(&(in_vertex_id, weight)) => {
+                                match joint_id(skeleton, in_vertex_id) {
+                                    Some(joint) => (joint, weight),
+                                    None => {
+                                        nb_unknown_joints += 1;
+                                        (0, 0.0)
+                                    }
+                                }
+                            }
+                            None => (0, 0.0),
+                        };
+                        joints.write_le_to_u16(joint)?;
+                        weights.write_le_to_f32(weight)?;
+                    }
+                }
+                let buffer_view_id =
+                    i_buffers.push(format!("mesh_{}_joints_{}.bin", mesh_counter, set), joints);
+                let joints_accessor_id = accessors.len();
+                accessors.push(Accessor {
+                    buffer_view: Some(buffer_view_id),
+                    component_type: 5123,
+                    count,
+                    attribute_type: "VEC4".to_owned(),
+                    min: None,
+                    max: None,
+                });
+                let buffer_view_id = i_buffers.push(
+                    format!("mesh_{}_weights_{}.bin", mesh_counter, set),
+                    weights,
+                );
+                let weights_accessor_id = accessors.len();
+                accessors.push(Accessor {
+                    buffer_view: Some(buffer_view_id),
+                    component_type: 5126,
+                    count,
+                    attribute_type: "VEC4".to_owned(),
+                    min: None,
+                    max: None,
+                });
+                skin_accessor_ids.push((joints_accessor_id, weights_accessor_id));
+            }
+        }
+        let mut attributes = BTreeMap::new();
+        attributes.insert("POSITION".to_owned(), positions_accessor_id);
+        attributes.insert("TEXCOORD_0".to_owned(), uv_maps_accessor_id);
+        attributes.insert("NORMAL".to_owned(), normals_accessor_id);
+        for (set, id) in extra_uv_maps_accessor_ids.iter().enumerate() {
+            attributes.insert(format!("TEXCOORD_{}", set + 1), *id);
+        }
+        if let Some(id) = colors_accessor_id {
+            attributes.insert("COLOR_0".to_owned(), id);
+        }
+        for (set, (joints, weights)) in skin_accessor_ids.iter().enumerate() {
+            attributes.insert(format!("JOINTS_{}", set), *joints);
+            attributes.insert(format!("WEIGHTS_{}", set), *weights);
+        }
+        group_nodes[i_mesh.group].push(nodes.len());
+        nodes.push(Node {
+            mesh: Some(mesh_counter),
+            children: None,
+            translation: None,
+            name: None,
+            // Only rigged meshes are skinned
+            skin: joints_inv_accessor_id
+                .filter(|_| !skin_accessor_ids.is_empty())
+                .map(|_| 0),
+            rotation: None,
+            scale: None,
+        });
+        meshes.push(Mesh {
+            primitives: vec![Primitive {
+                attributes,
+                indices: Some(accessor_id),
+                // Need to figure out at some point which mesh goes with which texture...
+                material: if materials.is_empty() { None } else { Some(0) },
+            }],
+        });
+    }
+    if nb_unknown_joints > 0 {
+        warnings.push(format!(
+            "Dropped {} influences of joints missing from the skeleton",
+            nb_unknown_joints
+        ));
+    }
+
+    // Each group is its own object, with a node referring to its Mesh nodes
+    let mut object_nodes = Vec::new();
+    for (id, children) in group_nodes.into_iter().enumerate() {
+        object_nodes.push(nodes.len());
+        nodes.push(Node {
+            mesh: None,
+            name: Some(format!("group_{}", id)),
+            rotation: None,
+            translation: None,
+            scale: None,
+            skin: None,
+            children: non_empty(children),
+        });
+    }
+
+    // This node will refer all group nodes as well as the armature root
+    let top_node = nodes.len();
+    nodes.push(Node {
+        mesh: None,
+        name: Some("top".to_owned()),
+        rotation: None,
+        translation: None,
+        scale: None,
+        skin: None,
+        children: non_empty({
+            // Add the armature root as a children (fixes some GlTF viewers)
+            let mut ch: Vec<usize> = root_node_id.into_iter().collect();
+            ch.append(&mut object_nodes);
+            ch
+        }),
+    });
+
+    // Push a single node for the entire scene (fixes some GlTF viewers)
+    let scenes = vec![Scene {
+        nodes: Some(vec![top_node]),
+    }];
+
+    Ok(GltfDocument {
+        gltf: GlTF {
+            asset: Asset {
+                version: "2.0".to_owned(),
+            },
+            scene: Some(0),
+            scenes: Some(scenes),
+            nodes: Some(nodes),
+            buffers: non_empty(i_buffers.buffers),
+            buffer_views: non_empty(i_buffers.buffer_views),
+            accessors: non_empty(accessors),
+            meshes: non_empty(meshes),
+            skins,
+            materials: non_empty(materials),
+            textures: non_empty(textures),
+            images: non_empty(images),
+        },
+        files: i_buffers.files,
+        warnings,
+    })
+}
+
+/// glTF does not allow empty arrays
+fn non_empty<T>(v: Vec<T>) -> Option<Vec<T>> {
+    if v.is_empty() {
+        None
+    } else {
+        Some(v)
+    }
+}
+
+/// Index of the joint with this in-vertex ID in the skin, which lists all joints
+fn joint_id(skeleton: &Skeleton, in_vertex_id: u16) -> Option<u16> {
+    skeleton
+        .find_by_in_vertex_id(u32::from(in_vertex_id))
+        .map(|id| id as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::joint_definition::{Joint, JointDefinition, JointDefinitionSubSection};
+    use crate::math::RotationOrder;
+    use crate::model_data::{
+        Faces, FrenetFrame, IndexFormat, Mesh as ModelMesh, MeshSubSection, ModelData, Topology,
+        Unnamed0A, Vector2D, Vector3D, VertexGeometry, VertexRigging, Vertices,
+        VerticesGeometryBuffer, VerticesRiggingBuffer,
+    };
+    use half::f16;
+
+    fn vertex(position: [f32; 3]) -> VertexGeometry {
+        let h = f16::from_f32;
+        VertexGeometry {
+            position_coordinates: Vector3D {
+                x: position[0],
+                y: position[1],
+                z: position[2],
+            },
+            texture_coordinates: Vector2D {
+                u: h(0.0),
+                v: h(0.0),
+            },
+            extra_texture_coordinates: Vec::new(),
+            frenet_frame: FrenetFrame {
+                normal: Vector3D {
+                    x: h(0.0),
+                    y: h(0.0),
+                    z: h(1.0),
+                },
+                tangent: Vector3D {
+                    x: h(1.0),
+                    y: h(0.0),
+                    z: h(0.0),
+                },
+            },
+            color: None,
+        }
+    }
+
+    fn triangle(indices: Vec<u32>) -> SubSection {
+        SubSection::Mesh(ModelMesh {
+            unknown: [0; 3],
+            nb_faces: 1,
+            sub_sections: vec![MeshSubSection::Faces(Faces {
+                header: [0x02, 0x04, 0],
+                index_format: IndexFormat::U16,
+                topology: Topology::TriangleList,
+                indices,
+            })],
+        })
+    }
+
+    fn geometry() -> SubSection {
+        SubSection::Vertices(Vertices {
+            nb_vertices: 3,
+            attributes: Vec::new(),
+            buffer: VerticesDataBuffer::Geometry(VerticesGeometryBuffer {
+                vertices: (0..3).map(|i| vertex([i as f32, 0.0, 0.0])).collect(),
+            }),
+        })
+    }
+
+    #[test]
+    fn to_memory_sink() {
+        let vertices: Vec<VertexGeometry> = (0..4)
+            .map(|i| vertex([i as f32, 2.0 * i as f32, -(i as f32)]))
+            .collect();
+        let group = Unnamed0A {
+            sub_sections: vec![
+                SubSection::Vertices(Vertices {
+                    nb_vertices: 4,
+                    attributes: Vec::new(),
+                    buffer: VerticesDataBuffer::Geometry(VerticesGeometryBuffer { vertices }),
+                }),
+                triangle(vec![1, 2, 3]),
+            ],
+        };
+        let ism = ISM2 {
+            version: 0,
+            file_size: 0,
+            string_table: Vec::new(),
+            sections: vec![Section::ModelData(ModelData {
+                groups: vec![group],
+            })],
+        };
+        let document = to_gltf(&ism, &Options::default()).unwrap();
+        let mut sink = MemorySink::default();
+        document.write(&mut sink, "model.gltf").unwrap();
+        assert!(sink.files.contains_key("model.gltf"));
+
+        let gltf = &document.gltf;
+        let accessors = gltf.accessors.as_ref().unwrap();
+        let primitive = &gltf.meshes.as_ref().unwrap()[0].primitives[0];
+        let indices = &accessors[primitive.indices.unwrap()];
+        assert_eq!(indices.count, 3);
+        // Only the vertices used by the faces are written
+        let positions = &accessors[primitive.attributes["POSITION"]];
+        assert_eq!(positions.count, 3);
+        assert_eq!(positions.min, Some(vec![1.0, 2.0, -3.0]));
+        assert_eq!(positions.max, Some(vec![3.0, 6.0, -1.0]));
+        assert_eq!(sink.files["mesh_0_positions.bin"].len(), 3 * 12);
+        assert!(gltf.skins.is_none());
+    }
+
+    #[test]
+    fn skin_without_joint_extra() {
+        let rigged = Unnamed0A {
+            sub_sections: vec![
+                geometry(),
+                SubSection::Vertices(Vertices {
+                    nb_vertices: 3,
+                    attributes: Vec::new(),
+                    buffer: VerticesDataBuffer::Rigging(VerticesRiggingBuffer {
+                        vertices: (0..3)
+                            .map(|_| VertexRigging {
+                                joints: [0; 8],
+                                weights: [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+                                nb_influences: 4,
+                            })
+                            .collect(),
+                    }),
+                }),
+                triangle(vec![0, 1, 2]),
+            ],
+        };
+        let unrigged = Unnamed0A {
+            sub_sections: vec![geometry(), triangle(vec![0, 1, 2])],
+        };
+        let ism = ISM2 {
+            version: 0,
+            file_size: 0,
+            string_table: Vec::new(),
+            sections: vec![
+                Section::JointDefinition(JointDefinition {
+                    sub_sections: vec![JointDefinitionSubSection::Joint(Joint {
+                        name: "root".to_string(),
+                        parent_index: None,
+                        sub_sections: Vec::new(),
+                        in_vertex_id: 0,
+                        rotation_order: RotationOrder::Xyz,
+                    })],
+                }),
+                Section::ModelData(ModelData {
+                    groups: vec![rigged, unrigged],
+                }),
+            ],
+        };
+        let document = to_gltf(&ism, &Options::default()).unwrap();
+        assert!(document
+            .warnings
+            .iter()
+            .any(|w| w.starts_with("No Joint Extra")));
+
+        let gltf = &document.gltf;
+        let skins = gltf.skins.as_ref().unwrap();
+        let accessors = gltf.accessors.as_ref().unwrap();
+        let inverse_bind_matrices = &accessors[skins[0].inverse_bind_matrices.unwrap()];
+        assert_eq!(inverse_bind_matrices.count, 1);
+        let meshes = gltf.meshes.as_ref().unwrap();
+        assert!(meshes[0].primitives[0].attributes.contains_key("JOINTS_0"));
+        assert!(!meshes[1].primitives[0].attributes.contains_key("JOINTS_0"));
+        let nodes = gltf.nodes.as_ref().unwrap();
+        let skin_of = |mesh: usize| {
+            nodes
+                .iter()
+                .find(|n| n.mesh == Some(mesh))
+                .map(|n| n.skin)
+                .unwrap()
+        };
+        assert_eq!(skin_of(0), Some(0));
+        assert_eq!(skin_of(1), None);
+    }
+}
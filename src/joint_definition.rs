@@ -1,51 +1,63 @@
 use crate::error::{ISM2ImportError, UnknownSubSection};
+use crate::math::RotationOrder;
 use ez_io::{MagicNumberCheck, ReadE};
 use std::collections::HashMap;
 use std::io::{Read, Seek, SeekFrom};
-use crate::Result;
+use crate::{read_raw_payload, Result};
 
 pub struct JointDefinition {
     pub sub_sections: Vec<JointDefinitionSubSection>,
 }
 
 pub enum JointDefinitionSubSection {
-    Unnamed04,
+    Unnamed04(Unnamed04),
     Joint(Joint),
 }
 
-// pub struct Unnamed04 {}
-
-// pub enum Unnamed04SubSection {
-//     Unnamed5B(Unnamed5B),
-//     Unnamed4C(Unnamed4C),
-// }
-
-// pub struct Unnamed5B {}
-
-// pub enum Unnamed5BSubSection {
-//     Unnamed5F(Unnamed5F),
-//     Unnamed5E(Unnamed5E),
-//     Unnamed5D(Unnamed5D),
-// }
-
-// pub struct Unnamed5F {}
-
-// pub struct Unnamed5E {}
+pub struct Unnamed04 {
+    /// Header words between the sub-sections count and the offsets table
+    pub unknown: Vec<u32>,
+    pub sub_sections: Vec<Unnamed04SubSection>,
+}
 
-// pub struct Unnamed5D {}
+pub enum Unnamed04SubSection {
+    /// Same table of attributes as the one found in joints
+    Unnamed5B(JointAttributesOffsets),
+    Unnamed4C(Unnamed4C),
+    /// Sub-section not known yet, only its magic number is kept
+    Unknown(u32),
+}
 
-// pub struct Unnamed4C {}
+pub struct Unnamed4C {
+    /// Everything after the size field, read as words
+    pub unknown: Vec<u32>,
+    /// Trailing bytes when the size is not a multiple of 4
+    pub raw: Vec<u8>,
+}
 
 pub struct Joint {
     pub name: String,
     pub parent_index: Option<usize>,
     pub sub_sections: Vec<JointSubSection>,
     pub in_vertex_id: u32,
+    /// Order the Rotate attributes are applied in. The file has no field for it, it is taken from the order of the
+    /// RotateX/Y/Z attributes in the offsets table, axes that have no attribute coming last in X, Y, Z order.
+    pub rotation_order: RotationOrder,
 }
 
 pub enum JointSubSection {
     Offsets(JointAttributesOffsets),
-    Unnamed5C,
+    Unnamed5C(Unnamed5C),
+}
+
+/// Table laid out like every other table of the format, 0x5B included:
+/// magic number, header size, number of entries, extra header words, then one offset per entry.
+/// Its meaning is not known, exporters ignore it.
+pub struct Unnamed5C {
+    /// Header words after the number of entries, when the header is larger than 0x0C
+    pub header: Vec<u32>,
+    /// Entries read as joint attributes, unknown ones keep their payload
+    pub entries: Vec<JointAttribute>,
 }
 
 pub struct JointAttributesOffsets {
@@ -61,23 +73,25 @@ pub enum JointAttribute {
     JointOrientX(JointOrientX),
     JointOrientY(JointOrientY),
     JointOrientZ(JointOrientZ),
-    CollisionFlag,
-    CollisionRadius,
-    PhysicsFlag,
-    PhysicsRadius,
-    PhysicsCost,
-    PhysicsMass,
-    PhysicsExpand,
-    PhysicsShapeMemory,
-    Unnamed7A,
-    Unnamed7B,
-    Unnamed7C,
-    Unnamed7D,
-    Unnamed7E,
+    CollisionFlag(JointCollisionFlag),
+    CollisionRadius(JointCollisionRadius),
+    PhysicsFlag(JointPhysicsFlag),
+    PhysicsRadius(JointPhysicsRadius),
+    PhysicsCost(JointPhysicsCost),
+    PhysicsMass(JointPhysicsMass),
+    PhysicsExpand(JointPhysicsExpand),
+    PhysicsShapeMemory(JointPhysicsShapeMemory),
+    Unnamed7A(JointRawAttribute),
+    Unnamed7B(JointRawAttribute),
+    Unnamed7C(JointRawAttribute),
+    Unnamed7D(JointRawAttribute),
+    Unnamed7E(JointRawAttribute),
+    Unknown(JointUnknownAttribute),
 }
 
 pub struct JointTranslate {
     // 0x14
+    pub header: u32,
     pub x: f32,
     pub y: f32,
     pub z: f32,
@@ -85,6 +99,7 @@ pub struct JointTranslate {
 
 pub struct JointScale {
     // 0x15
+    pub header: u32,
     pub x: f32,
     pub y: f32,
     pub z: f32,
@@ -92,34 +107,104 @@ pub struct JointScale {
 
 pub struct JointRotateX {
     // 0x5D
+    pub header: [u32; 4],
     pub angle: f32,
 }
 
 pub struct JointRotateY {
     // 0x5E
+    pub header: [u32; 4],
     pub angle: f32,
 }
 
 pub struct JointRotateZ {
     // 0x5F
+    pub header: [u32; 4],
     pub angle: f32,
 }
 
 pub struct JointOrientX {
     // 0x67
+    pub header: [u32; 4],
     pub angle: f32,
 }
 
 pub struct JointOrientY {
     // 0x68
+    pub header: [u32; 4],
     pub angle: f32,
 }
 
 pub struct JointOrientZ {
     // 0x69
+    pub header: [u32; 4],
     pub angle: f32,
 }
 
+pub struct JointCollisionFlag {
+    // 0x70
+    pub header: [u32; 4],
+    pub value: u32,
+}
+
+pub struct JointCollisionRadius {
+    // 0x71
+    pub header: [u32; 4],
+    pub radius: f32,
+}
+
+pub struct JointPhysicsFlag {
+    // 0x72
+    pub header: [u32; 4],
+    pub value: u32,
+}
+
+pub struct JointPhysicsRadius {
+    // 0x73
+    pub header: [u32; 4],
+    pub radius: f32,
+}
+
+pub struct JointPhysicsCost {
+    // 0x74
+    pub header: [u32; 4],
+    pub cost: f32,
+}
+
+pub struct JointPhysicsMass {
+    // 0x75
+    pub header: [u32; 4],
+    pub mass: f32,
+}
+
+pub struct JointPhysicsExpand {
+    // 0x76
+    pub header: [u32; 4],
+    pub expand: f32,
+}
+
+pub struct JointPhysicsShapeMemory {
+    // 0x77
+    pub header: [u32; 4],
+    pub shape_memory: f32,
+}
+
+/// Attribute whose layout is not understood yet, kept as the fixed-size record following its magic number
+pub struct JointRawAttribute {
+    // 0x7A to 0x7E
+    pub data: [u32; 5],
+}
+
+/// Attribute not known yet, read like the other records of unknown content:
+/// magic number, size in bytes, then the payload kept as found in the file
+pub struct JointUnknownAttribute {
+    pub magic_number: u32,
+    /// Everything after the size field, read as words
+    pub unknown: Vec<u32>,
+    /// Trailing bytes when the size is not a multiple of 4
+    pub raw: Vec<u8>,
+}
+
 // pub struct Research {}
 
 impl JointDefinition {
@@ -137,41 +222,73 @@ impl JointDefinition {
         }
         let mut sub_sections = Vec::with_capacity(nb_sub_sections as usize);
         let mut offset_index_map = HashMap::new();
-        let mut offset_index_map_counter = 0usize;
+        let mut parent_offsets = Vec::new();
         for o in offsets {
             reader.seek(SeekFrom::Start(u64::from(o)))?;
             sub_sections.push(JointDefinitionSubSection::import(
                 reader,
                 strings_table,
                 &mut offset_index_map,
-                &mut offset_index_map_counter,
+                &mut parent_offsets,
             )?);
         }
+        // Parents may come after their children, so they can only be resolved once all joints are read
+        let mut parent_indices = Vec::with_capacity(parent_offsets.len());
+        for parent_offset in parent_offsets {
+            parent_indices.push(if parent_offset == 0 {
+                None
+            } else {
+                match offset_index_map.get(&u64::from(parent_offset)) {
+                    Some(id) => Some(*id),
+                    None => return Err(ISM2ImportError::InvalidParentOffset(parent_offset)),
+                }
+            });
+        }
+        let mut joints: Vec<&mut Joint> = sub_sections
+            .iter_mut()
+            .filter_map(|s| match s {
+                JointDefinitionSubSection::Joint(j) => Some(j),
+                _ => None,
+            })
+            .collect();
+        for id in 0..parent_indices.len() {
+            // A joint cannot have more ancestors than there are joints
+            let mut current = parent_indices[id];
+            for _ in 0..parent_indices.len() {
+                current = match current {
+                    Some(p) => parent_indices[p],
+                    None => break,
+                };
+            }
+            if current.is_some() {
+                return Err(ISM2ImportError::JointCycle(joints[id].name.clone()));
+            }
+        }
+        for (joint, parent_index) in joints.iter_mut().zip(parent_indices) {
+            joint.parent_index = parent_index;
+        }
         Ok(JointDefinition { sub_sections })
     }
 }
 
 impl JointDefinitionSubSection {
-    pub fn import<R: Read + Seek>(
+    /// Joints are read with no parent, see `Joint::import`
+    pub(crate) fn import<R: Read + Seek>(
         reader: &mut R,
         strings_table: &[String],
         offset_index_map: &mut HashMap<u64, usize>,
-        offset_index_map_counter: &mut usize,
+        parent_offsets: &mut Vec<u32>,
     ) -> Result<JointDefinitionSubSection> {
         let magic_number = reader.read_le_to_u32()?;
         reader.seek(SeekFrom::Current(-4))?;
         Ok(match magic_number {
-            0x04 => JointDefinitionSubSection::Unnamed04,
+            0x04 => JointDefinitionSubSection::Unnamed04(Unnamed04::import(reader)?),
             0x05 => {
-                offset_index_map.insert(
-                    reader.seek(SeekFrom::Current(0))?,
-                    *offset_index_map_counter,
-                );
-                *offset_index_map_counter += 1;
+                offset_index_map.insert(reader.stream_position()?, parent_offsets.len());
                 JointDefinitionSubSection::Joint(Joint::import(
                     reader,
                     strings_table,
-                    offset_index_map,
+                    parent_offsets,
                 )?)
             }
             x => {
@@ -184,26 +301,64 @@ impl JointDefinitionSubSection {
     }
 }
 
+impl Unnamed04 {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<Unnamed04> {
+        reader.check_magic_number(&[0x04, 0, 0, 0])?;
+        let header_size = reader.read_le_to_u32()?;
+        let nb_sub_sections = reader.read_le_to_u32()?;
+        let mut unknown = Vec::new();
+        for _ in 0..(header_size.saturating_sub(0x0C) / 4) {
+            unknown.push(reader.read_le_to_u32()?);
+        }
+        let mut offsets = Vec::with_capacity(nb_sub_sections as usize);
+        for _ in 0..nb_sub_sections {
+            offsets.push(reader.read_le_to_u32()?);
+        }
+        let mut sub_sections = Vec::with_capacity(nb_sub_sections as usize);
+        for o in offsets {
+            reader.seek(SeekFrom::Start(u64::from(o)))?;
+            sub_sections.push(Unnamed04SubSection::import(reader)?);
+        }
+        Ok(Unnamed04 {
+            unknown,
+            sub_sections,
+        })
+    }
+}
+
+impl Unnamed04SubSection {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<Unnamed04SubSection> {
+        let magic_number = reader.read_le_to_u32()?;
+        reader.seek(SeekFrom::Current(-4))?;
+        Ok(match magic_number {
+            0x5B => Unnamed04SubSection::Unnamed5B(JointAttributesOffsets::import(reader)?),
+            0x4C => Unnamed04SubSection::Unnamed4C(Unnamed4C::import(reader)?),
+            x => Unnamed04SubSection::Unknown(x),
+        })
+    }
+}
+
+impl Unnamed4C {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<Unnamed4C> {
+        let (unknown, raw) = read_raw_payload(reader, 0x4C)?;
+        Ok(Unnamed4C { unknown, raw })
+    }
+}
+
 impl Joint {
-    pub fn import<R: Read + Seek>(
+    /// Reads a joint with `parent_index` left to None. The offset of its parent is pushed to `parent_offsets`,
+    /// `JointDefinition::import` turns it into an index once every joint is read.
+    pub(crate) fn import<R: Read + Seek>(
         reader: &mut R,
         strings_table: &[String],
-        offset_index_map: &mut HashMap<u64, usize>,
+        parent_offsets: &mut Vec<u32>,
     ) -> Result<Joint> {
         reader.check_magic_number(&[0x05, 0, 0, 0, 0x40, 0, 0, 0])?;
         let nb_sub_sections = reader.read_le_to_u32()?;
         let string_table_index = reader.read_le_to_u32()?;
         let name = strings_table[string_table_index as usize].clone();
         reader.seek(SeekFrom::Current(0xC))?;
-        let parent_joint_offset = reader.read_le_to_u32()?;
-        let parent_index = if parent_joint_offset == 0 {
-            None
-        } else {
-            match offset_index_map.get(&u64::from(parent_joint_offset)) {
-                Some(id) => Some(*id),
-                None => panic!("This should not happen"),
-            }
-        };
+        parent_offsets.push(reader.read_le_to_u32()?); // Resolved by JointDefinition
         reader.seek(SeekFrom::Current(12))?;
         let in_vertex_id = reader.read_le_to_u32()?;
         reader.seek(SeekFrom::Current(16))?;
@@ -216,22 +371,68 @@ impl Joint {
             reader.seek(SeekFrom::Start(u64::from(o)))?;
             sub_sections.push(JointSubSection::import(reader)?);
         }
-        Ok(Joint {
+        let mut joint = Joint {
             name,
-            parent_index,
+            parent_index: None,
             sub_sections,
             in_vertex_id,
+            rotation_order: RotationOrder::default(),
+        };
+        joint.rotation_order = rotation_order_of(joint.attributes());
+        Ok(joint)
+    }
+
+    /// Iterates over all attributes of this joint, in file order
+    pub fn attributes(&self) -> impl Iterator<Item = &JointAttribute> {
+        self.sub_sections
+            .iter()
+            .filter_map(|s| match s {
+                JointSubSection::Offsets(o) => Some(o.attributes.iter()),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    /// Returns the 0x5C sub-section of this joint, if there is one
+    pub fn unnamed_5c(&self) -> Option<&Unnamed5C> {
+        self.sub_sections.iter().find_map(|s| match s {
+            JointSubSection::Unnamed5C(u) => Some(u),
+            _ => None,
         })
     }
 }
 
+/// Rotation order given by the order the Rotate attributes appear in
+pub(crate) fn rotation_order_of<'a, I: Iterator<Item = &'a JointAttribute>>(
+    attributes: I,
+) -> RotationOrder {
+    let mut axes: Vec<usize> = Vec::with_capacity(3);
+    for attribute in attributes {
+        let axis = match attribute {
+            JointAttribute::RotateX(_) => 0,
+            JointAttribute::RotateY(_) => 1,
+            JointAttribute::RotateZ(_) => 2,
+            _ => continue,
+        };
+        if !axes.contains(&axis) {
+            axes.push(axis);
+        }
+    }
+    for axis in 0..3 {
+        if !axes.contains(&axis) {
+            axes.push(axis);
+        }
+    }
+    RotationOrder::from_axes([axes[0], axes[1], axes[2]]).unwrap_or_default()
+}
+
 impl JointSubSection {
     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointSubSection> {
         let magic_number = reader.read_le_to_u32()?;
         reader.seek(SeekFrom::Current(-4))?;
         Ok(match magic_number {
             0x5B => JointSubSection::Offsets(JointAttributesOffsets::import(reader)?),
-            0x5C => JointSubSection::Unnamed5C,
+            0x5C => JointSubSection::Unnamed5C(Unnamed5C::import(reader)?),
             x => {
                 return Err(ISM2ImportError::UnknownSubSection(UnknownSubSection {
                     in_section: 0x05,
@@ -242,6 +443,28 @@ impl JointSubSection {
     }
 }
 
+impl Unnamed5C {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<Unnamed5C> {
+        reader.check_magic_number(&[0x5C, 0, 0, 0])?;
+        let header_size = reader.read_le_to_u32()?;
+        let nb_entries = reader.read_le_to_u32()?;
+        let mut header = Vec::new();
+        for _ in 0..(header_size.saturating_sub(0x0C) / 4) {
+            header.push(reader.read_le_to_u32()?);
+        }
+        let mut offsets = Vec::with_capacity(nb_entries as usize);
+        for _ in 0..nb_entries {
+            offsets.push(reader.read_le_to_u32()?);
+        }
+        let mut entries = Vec::with_capacity(nb_entries as usize);
+        for o in offsets {
+            reader.seek(SeekFrom::Start(u64::from(o)))?;
+            entries.push(JointAttribute::import(reader)?);
+        }
+        Ok(Unnamed5C { header, entries })
+    }
+}
+
 impl JointAttributesOffsets {
     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointAttributesOffsets> {
         reader.check_magic_number(&[0x5B, 0, 0, 0, 0x0C, 0, 0, 0])?;
@@ -272,24 +495,26 @@ impl JointAttribute {
             0x67 => JointAttribute::JointOrientX(JointOrientX::import(reader)?),
             0x68 => JointAttribute::JointOrientY(JointOrientY::import(reader)?),
             0x69 => JointAttribute::JointOrientZ(JointOrientZ::import(reader)?),
-            0x70 => JointAttribute::CollisionFlag,
-            0x71 => JointAttribute::CollisionRadius,
-            0x72 => JointAttribute::PhysicsFlag,
-            0x73 => JointAttribute::PhysicsRadius,
-            0x74 => JointAttribute::PhysicsCost,
-            0x75 => JointAttribute::PhysicsMass,
-            0x76 => JointAttribute::PhysicsExpand,
-            0x77 => JointAttribute::PhysicsShapeMemory,
-            0x7A => JointAttribute::Unnamed7A,
-            0x7B => JointAttribute::Unnamed7B,
-            0x7C => JointAttribute::Unnamed7C,
-            0x7D => JointAttribute::Unnamed7D,
-            0x7E => JointAttribute::Unnamed7E,
+            0x70 => JointAttribute::CollisionFlag(JointCollisionFlag::import(reader)?),
+            0x71 => JointAttribute::CollisionRadius(JointCollisionRadius::import(reader)?),
+            0x72 => JointAttribute::PhysicsFlag(JointPhysicsFlag::import(reader)?),
+            0x73 => JointAttribute::PhysicsRadius(JointPhysicsRadius::import(reader)?),
+            0x74 => JointAttribute::PhysicsCost(JointPhysicsCost::import(reader)?),
+            0x75 => JointAttribute::PhysicsMass(JointPhysicsMass::import(reader)?),
+            0x76 => JointAttribute::PhysicsExpand(JointPhysicsExpand::import(reader)?),
+            0x77 => JointAttribute::PhysicsShapeMemory(JointPhysicsShapeMemory::import(reader)?),
+            0x7A => JointAttribute::Unnamed7A(JointRawAttribute::import(reader, 0x7A)?),
+            0x7B => JointAttribute::Unnamed7B(JointRawAttribute::import(reader, 0x7B)?),
+            0x7C => JointAttribute::Unnamed7C(JointRawAttribute::import(reader, 0x7C)?),
+            0x7D => JointAttribute::Unnamed7D(JointRawAttribute::import(reader, 0x7D)?),
+            0x7E => JointAttribute::Unnamed7E(JointRawAttribute::import(reader, 0x7E)?),
             x => {
-                return Err(ISM2ImportError::UnknownSubSection(UnknownSubSection {
-                    in_section: 0x5B,
-                    failed_to_match: x,
-                }))
+                let (unknown, raw) = read_raw_payload(reader, x)?;
+                JointAttribute::Unknown(JointUnknownAttribute {
+                    magic_number: x,
+                    unknown,
+                    raw,
+                })
             }
         })
     }
@@ -298,79 +523,190 @@ impl JointAttribute {
 impl JointTranslate {
     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointTranslate> {
         reader.check_magic_number(&[0x14, 0, 0, 0])?;
-        reader.seek(SeekFrom::Current(4))?;
+        let header = reader.read_le_to_u32()?;
         let x = reader.read_le_to_f32()?;
         let y = reader.read_le_to_f32()?;
         let z = reader.read_le_to_f32()?;
-        Ok(JointTranslate { x, y, z })
+        Ok(JointTranslate { header, x, y, z })
     }
 }
 
 impl JointScale {
     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointScale> {
         reader.check_magic_number(&[0x15, 0, 0, 0])?;
-        reader.seek(SeekFrom::Current(4))?;
+        let header = reader.read_le_to_u32()?;
         let x = reader.read_le_to_f32()?;
         let y = reader.read_le_to_f32()?;
         let z = reader.read_le_to_f32()?;
-        Ok(JointScale { x, y, z })
+        Ok(JointScale { header, x, y, z })
     }
 }
 
 impl JointRotateX {
     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointRotateX> {
         reader.check_magic_number(&[0x5D, 0, 0, 0])?;
-        reader.seek(SeekFrom::Current(16))?;
+        let header = read_attribute_header(reader)?;
         let angle = reader.read_le_to_f32()?;
-        Ok(JointRotateX { angle })
+        Ok(JointRotateX { header, angle })
     }
 }
 
 impl JointRotateY {
     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointRotateY> {
         reader.check_magic_number(&[0x5E, 0, 0, 0])?;
-        reader.seek(SeekFrom::Current(16))?;
+        let header = read_attribute_header(reader)?;
         let angle = reader.read_le_to_f32()?;
-        Ok(JointRotateY { angle })
+        Ok(JointRotateY { header, angle })
     }
 }
 
 impl JointRotateZ {
     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointRotateZ> {
         reader.check_magic_number(&[0x5F, 0, 0, 0])?;
-        reader.seek(SeekFrom::Current(16))?;
+        let header = read_attribute_header(reader)?;
         let angle = reader.read_le_to_f32()?;
-        Ok(JointRotateZ { angle })
+        Ok(JointRotateZ { header, angle })
     }
 }
 
 impl JointOrientX {
     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointOrientX> {
         reader.check_magic_number(&[0x67, 0, 0, 0])?;
-        reader.seek(SeekFrom::Current(16))?;
+        let header = read_attribute_header(reader)?;
         let angle = reader.read_le_to_f32()?;
-        Ok(JointOrientX { angle })
+        Ok(JointOrientX { header, angle })
     }
 }
 
 impl JointOrientY {
     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointOrientY> {
         reader.check_magic_number(&[0x68, 0, 0, 0])?;
-        reader.seek(SeekFrom::Current(16))?;
+        let header = read_attribute_header(reader)?;
         let angle = reader.read_le_to_f32()?;
-        Ok(JointOrientY { angle })
+        Ok(JointOrientY { header, angle })
     }
 }
 
 impl JointOrientZ {
     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointOrientZ> {
         reader.check_magic_number(&[0x69, 0, 0, 0])?;
-        reader.seek(SeekFrom::Current(16))?;
+        let header = read_attribute_header(reader)?;
         let angle = reader.read_le_to_f32()?;
-        Ok(JointOrientZ { angle })
+        Ok(JointOrientZ { header, angle })
+    }
+}
+
+impl JointCollisionFlag {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointCollisionFlag> {
+        reader.check_magic_number(&[0x70, 0, 0, 0])?;
+        let header = read_attribute_header(reader)?;
+        let value = reader.read_le_to_u32()?;
+        Ok(JointCollisionFlag { header, value })
+    }
+
+    /// Whether the flag is enabled
+    pub fn is_set(&self) -> bool {
+        self.value != 0
+    }
+}
+
+impl JointCollisionRadius {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointCollisionRadius> {
+        reader.check_magic_number(&[0x71, 0, 0, 0])?;
+        let header = read_attribute_header(reader)?;
+        let radius = reader.read_le_to_f32()?;
+        Ok(JointCollisionRadius { header, radius })
+    }
+}
+
+impl JointPhysicsFlag {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointPhysicsFlag> {
+        reader.check_magic_number(&[0x72, 0, 0, 0])?;
+        let header = read_attribute_header(reader)?;
+        let value = reader.read_le_to_u32()?;
+        Ok(JointPhysicsFlag { header, value })
+    }
+
+    /// Whether the flag is enabled
+    pub fn is_set(&self) -> bool {
+        self.value != 0
+    }
+}
+
+impl JointPhysicsRadius {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointPhysicsRadius> {
+        reader.check_magic_number(&[0x73, 0, 0, 0])?;
+        let header = read_attribute_header(reader)?;
+        let radius = reader.read_le_to_f32()?;
+        Ok(JointPhysicsRadius { header, radius })
+    }
+}
+
+impl JointPhysicsCost {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointPhysicsCost> {
+        reader.check_magic_number(&[0x74, 0, 0, 0])?;
+        let header = read_attribute_header(reader)?;
+        let cost = reader.read_le_to_f32()?;
+        Ok(JointPhysicsCost { header, cost })
+    }
+}
+
+impl JointPhysicsMass {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointPhysicsMass> {
+        reader.check_magic_number(&[0x75, 0, 0, 0])?;
+        let header = read_attribute_header(reader)?;
+        let mass = reader.read_le_to_f32()?;
+        Ok(JointPhysicsMass { header, mass })
+    }
+}
+
+impl JointPhysicsExpand {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointPhysicsExpand> {
+        reader.check_magic_number(&[0x76, 0, 0, 0])?;
+        let header = read_attribute_header(reader)?;
+        let expand = reader.read_le_to_f32()?;
+        Ok(JointPhysicsExpand { header, expand })
+    }
+}
+
+impl JointPhysicsShapeMemory {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<JointPhysicsShapeMemory> {
+        reader.check_magic_number(&[0x77, 0, 0, 0])?;
+        let header = read_attribute_header(reader)?;
+        let shape_memory = reader.read_le_to_f32()?;
+        Ok(JointPhysicsShapeMemory {
+            header,
+            shape_memory,
+        })
     }
 }
 
+impl JointRawAttribute {
+    pub fn import<R: Read + Seek>(reader: &mut R, magic_number: u8) -> Result<JointRawAttribute> {
+        reader.check_magic_number(&[magic_number, 0, 0, 0])?;
+        let mut data = [0u32; 5];
+        for d in data.iter_mut() {
+            *d = reader.read_le_to_u32()?;
+        }
+        Ok(JointRawAttribute { data })
+    }
+
+    /// Last word of the record, which holds the value for all other scalar attributes
+    pub fn value_as_f32(&self) -> f32 {
+        f32::from_bits(self.data[4])
+    }
+}
+
+/// Reads the 16 bytes found between the magic number and the value of scalar attributes
+fn read_attribute_header<R: Read>(reader: &mut R) -> Result<[u32; 4]> {
+    Ok([
+        reader.read_le_to_u32()?,
+        reader.read_le_to_u32()?,
+        reader.read_le_to_u32()?,
+        reader.read_le_to_u32()?,
+    ])
+}
+
 // impl Research {
 //     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<Research> {
 //         let o = reader.seek(SeekFrom::Current(0))?;
@@ -379,3 +715,181 @@ impl JointOrientZ {
 //         Ok(Research {})
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn bytes(words: &[u32]) -> Cursor<Vec<u8>> {
+        Cursor::new(
+            words
+                .iter()
+                .flat_map(|w| w.to_le_bytes().to_vec())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn unnamed_04() {
+        #[rustfmt::skip]
+        let words = [
+            // 0x00: Unnamed04 with one extra header word and 3 sub-sections
+            0x04, 0x10, 3, 0xAB, 0x1C, 0x84, 0x94,
+            // 0x1C: attributes table
+            0x5B, 0x0C, 4, 0x38, 0x50, 0x64, 0x7C,
+            // 0x38: RotateX
+            0x5D, 1, 2, 3, 4, 1.5f32.to_bits(),
+            // 0x50: Translate
+            0x14, 0x14, 1f32.to_bits(), 2f32.to_bits(), 3f32.to_bits(),
+            // 0x64: JointOrientZ
+            0x69, 0, 0, 0, 0, 0.5f32.to_bits(),
+            // 0x7C: unknown attribute without payload
+            0x99, 0x08,
+            // 0x84: Unnamed4C
+            0x4C, 0x10, 7, 8,
+            // 0x94: unknown sub-section
+            0x88, 0,
+        ];
+        let unnamed_04 = Unnamed04::import(&mut bytes(&words)).unwrap();
+        assert_eq!(unnamed_04.unknown, vec![0xAB]);
+        assert_eq!(unnamed_04.sub_sections.len(), 3);
+        let attributes = match &unnamed_04.sub_sections[0] {
+            Unnamed04SubSection::Unnamed5B(o) => &o.attributes,
+            _ => panic!("Expected an attributes table"),
+        };
+        assert_eq!(attributes.len(), 4);
+        match &attributes[0] {
+            JointAttribute::RotateX(r) => {
+                assert_eq!(r.header, [1, 2, 3, 4]);
+                assert_eq!(r.angle, 1.5);
+            }
+            _ => panic!("Expected RotateX"),
+        }
+        match &attributes[1] {
+            JointAttribute::Translate(t) => assert_eq!([t.x, t.y, t.z], [1.0, 2.0, 3.0]),
+            _ => panic!("Expected Translate"),
+        }
+        match &attributes[2] {
+            JointAttribute::JointOrientZ(o) => assert_eq!(o.angle, 0.5),
+            _ => panic!("Expected JointOrientZ"),
+        }
+        match &attributes[3] {
+            JointAttribute::Unknown(u) => {
+                assert_eq!(u.magic_number, 0x99);
+                assert!(u.unknown.is_empty());
+            }
+            _ => panic!("Expected an unknown attribute"),
+        }
+        match &unnamed_04.sub_sections[1] {
+            Unnamed04SubSection::Unnamed4C(u) => assert_eq!(u.unknown, vec![7, 8]),
+            _ => panic!("Expected Unnamed4C"),
+        }
+        match &unnamed_04.sub_sections[2] {
+            Unnamed04SubSection::Unknown(magic_number) => assert_eq!(*magic_number, 0x88),
+            _ => panic!("Expected an unknown sub-section"),
+        }
+    }
+
+    #[test]
+    fn unnamed_5c() {
+        #[rustfmt::skip]
+        let words = [
+            // 0x00: table with one extra header word and 2 entries
+            0x5C, 0x10, 2, 0xCD, 0x18, 0x30,
+            // 0x18: CollisionRadius
+            0x71, 0, 0, 0, 0, 0.25f32.to_bits(),
+            // 0x30: unknown entry of 0x10 bytes
+            0x99, 0x10, 7, 8,
+        ];
+        let unnamed_5c = Unnamed5C::import(&mut bytes(&words)).unwrap();
+        assert_eq!(unnamed_5c.header, vec![0xCD]);
+        assert_eq!(unnamed_5c.entries.len(), 2);
+        match &unnamed_5c.entries[0] {
+            JointAttribute::CollisionRadius(c) => assert_eq!(c.radius, 0.25),
+            _ => panic!("Expected CollisionRadius"),
+        }
+        match &unnamed_5c.entries[1] {
+            JointAttribute::Unknown(u) => {
+                assert_eq!(u.magic_number, 0x99);
+                assert_eq!(u.unknown, vec![7, 8]);
+                assert!(u.raw.is_empty());
+            }
+            _ => panic!("Expected an unknown entry"),
+        }
+    }
+
+    #[test]
+    fn joint_rotation_order() {
+        #[rustfmt::skip]
+        let words = [
+            // 0x00: joint named by string 1, parent at 0x200, in-vertex ID 7, one sub-section
+            0x05, 0x40, 1, 1, 0, 0, 0, 0x200, 0, 0, 0, 7, 0, 0, 0, 0, 0x44,
+            // 0x44: attributes table, Rotate attributes in Z, X, Y order
+            0x5B, 0x0C, 3, 0x5C, 0x74, 0x8C,
+            // 0x5C: RotateZ
+            0x5F, 0, 0, 0, 0, 30f32.to_bits(),
+            // 0x74: RotateX
+            0x5D, 0, 0, 0, 0, 10f32.to_bits(),
+            // 0x8C: RotateY
+            0x5E, 0, 0, 0, 0, 20f32.to_bits(),
+        ];
+        let strings = vec![String::new(), "joint".to_string()];
+        let mut parent_offsets = Vec::new();
+        let joint = Joint::import(&mut bytes(&words), &strings, &mut parent_offsets).unwrap();
+        assert_eq!(joint.name, "joint");
+        assert_eq!(joint.in_vertex_id, 7);
+        assert_eq!(parent_offsets, vec![0x200]);
+        assert_eq!(joint.rotation_order, RotationOrder::Zxy);
+    }
+
+    /// Two joints without sub-sections, at 0x1C and 0x5C, with the given parent offsets
+    fn two_joints(parent_offsets: [u32; 2]) -> Result<JointDefinition> {
+        #[rustfmt::skip]
+        let words = [
+            // 0x00: two sub-sections
+            0x03, 0x14, 2, 0, 0, 0x1C, 0x5C,
+            // 0x1C: first joint, string 1
+            0x05, 0x40, 0, 1, 0, 0, 0, parent_offsets[0], 0, 0, 0, 0, 0, 0, 0, 0,
+            // 0x5C: second joint, string 2
+            0x05, 0x40, 0, 2, 0, 0, 0, parent_offsets[1], 0, 0, 0, 1, 0, 0, 0, 0,
+        ];
+        let strings = vec![String::new(), "first".to_string(), "second".to_string()];
+        JointDefinition::import(&mut bytes(&words), &strings)
+    }
+
+    fn parent_indices(joint_definition: &JointDefinition) -> Vec<Option<usize>> {
+        joint_definition
+            .sub_sections
+            .iter()
+            .filter_map(|s| match s {
+                JointDefinitionSubSection::Joint(j) => Some(j.parent_index),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn child_before_parent() {
+        let joint_definition = two_joints([0x5C, 0]).unwrap();
+        assert_eq!(parent_indices(&joint_definition), vec![Some(1), None]);
+        let joint_definition = two_joints([0, 0x1C]).unwrap();
+        assert_eq!(parent_indices(&joint_definition), vec![None, Some(0)]);
+    }
+
+    #[test]
+    fn invalid_parent_offset() {
+        match two_joints([0, 0x60]) {
+            Err(ISM2ImportError::InvalidParentOffset(offset)) => assert_eq!(offset, 0x60),
+            _ => panic!("Expected an invalid parent offset"),
+        }
+    }
+
+    #[test]
+    fn joint_cycle() {
+        match two_joints([0x5C, 0x1C]) {
+            Err(ISM2ImportError::JointCycle(name)) => assert_eq!(name, "first"),
+            _ => panic!("Expected a joint cycle"),
+        }
+    }
+}
@@ -1,5 +1,5 @@
-use crate::error::ISM2ImportError;
 use ez_io::{MagicNumberCheck, ReadE};
+use std::collections::HashMap;
 use std::io::{Read, Seek, SeekFrom};
 use crate::Result;
 
@@ -14,17 +14,52 @@ pub struct Unnamed31 {
 }
 
 pub struct Unnamed30 {
+    pub unknown1: u32,
     pub identity_matrix: [f32; 16],
     pub sub_sections: Vec<Buffer>,
 }
 
+/// A flat list of values, `components` values making up one element.
+/// Bone names are string references with 1 component, inverse bind matrices are f32 with 16 components.
 pub struct Buffer {
+    pub element_type: ElementType,
+    pub components: u32,
+    /// Number of values from the start of an element to the next, 0 when they are packed.
+    /// Only 0 for string references and `components` for f32 have been seen so far.
+    pub stride: u32,
+    /// Values of the elements, without the padding the stride might add
     pub data: BufferData,
 }
 
+/// Type of the values of a buffer, only two type codes have been seen so far
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElementType {
+    F32,
+    /// u16 index in the string table
+    StringReference,
+    /// Type code not known yet
+    Unknown(u32),
+}
+
 pub enum BufferData {
-    BoneNames(Vec<String>),
-    InverseBindMatrices(Vec<[f32; 16]>),
+    F32(Vec<f32>),
+    Strings(Vec<String>),
+    /// Values of an unknown type, read as words, padding included
+    Raw(Vec<u32>),
+}
+
+/// A list of bones and their inverse bind matrices, as found in a single Unnamed30.
+/// The i-th inverse bind matrix belongs to the i-th bone.
+pub struct SkinBinding<'a> {
+    /// First name of the parent Unnamed31, looks like the name of the skinned mesh
+    pub name1: &'a str,
+    /// Second name of the parent Unnamed31, looks like the name of the skin cluster
+    pub name2: &'a str,
+    /// Has always been an identity matrix so far, maybe the bind matrix of the mesh itself
+    pub matrix: &'a [f32; 16],
+    pub unknown1: u32,
+    pub bone_names: &'a [String],
+    pub inverse_bind_matrices: Vec<[f32; 16]>,
 }
 
 impl JointExtra {
@@ -43,6 +78,51 @@ impl JointExtra {
         }
         Ok(JointExtra { sub_sections })
     }
+
+    /// Lists every Unnamed30 as a skin binding, in file order
+    pub fn skin_bindings(&self) -> Vec<SkinBinding<'_>> {
+        let mut bindings = Vec::new();
+        for s31 in &self.sub_sections {
+            for s30 in &s31.sub_sections {
+                let mut bone_names: &[String] = &[];
+                let mut inverse_bind_matrices = Vec::new();
+                for buffer in &s30.sub_sections {
+                    if let Some(n) = buffer.bone_names() {
+                        bone_names = n;
+                    } else if let Some(m) = buffer.inverse_bind_matrices() {
+                        inverse_bind_matrices = m;
+                    }
+                }
+                bindings.push(SkinBinding {
+                    name1: &s31.name1,
+                    name2: &s31.name2,
+                    matrix: &s30.identity_matrix,
+                    unknown1: s30.unknown1,
+                    bone_names,
+                    inverse_bind_matrices,
+                });
+            }
+        }
+        bindings
+    }
+}
+
+impl<'a> SkinBinding<'a> {
+    /// Maps every bone name of this binding to its inverse bind matrix
+    pub fn bone_inverse_bind_matrices(&self) -> HashMap<&'a str, &[f32; 16]> {
+        self.bone_names
+            .iter()
+            .map(|n| n.as_str())
+            .zip(self.inverse_bind_matrices.iter())
+            .collect()
+    }
+
+    pub fn inverse_bind_matrix(&self, bone_name: &str) -> Option<&[f32; 16]> {
+        self.bone_names
+            .iter()
+            .position(|n| n == bone_name)
+            .and_then(|i| self.inverse_bind_matrices.get(i))
+    }
 }
 
 impl Unnamed31 {
@@ -72,7 +152,7 @@ impl Unnamed30 {
     pub fn import<R: Read + Seek>(reader: &mut R, strings_table: &[String]) -> Result<Unnamed30> {
         reader.check_magic_number(&[0x30, 0x00, 0x00, 0x00, 0x54, 0x00, 0x00, 0x00])?; // Magic Number + 0x54
         let nb_sub_sections = reader.read_le_to_u32()?;
-        let _unk1 = reader.read_le_to_u32()?;
+        let unknown1 = reader.read_le_to_u32()?;
         reader.check_magic_number(&[0u8; 4])?;
         let mut identity_matrix = [0f32; 16];
         for i in 0..16 {
@@ -88,6 +168,7 @@ impl Unnamed30 {
             sub_sections.push(Buffer::import(reader, strings_table)?);
         }
         Ok(Unnamed30 {
+            unknown1,
             identity_matrix,
             sub_sections,
         })
@@ -97,36 +178,172 @@ impl Unnamed30 {
 impl Buffer {
     pub fn import<R: Read + Seek>(reader: &mut R, strings_table: &[String]) -> Result<Buffer> {
         reader.check_magic_number(&[0x44, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00])?; // Magic Number + 0x20
-        let nb_entries = reader.read_le_to_u32()?;
+        let nb_values = reader.read_le_to_u32()?;
         reader.check_magic_number(&[0u8; 4])?;
-        let part1 = reader.read_le_to_u32()?;
-        let part2 = reader.read_le_to_u32()?;
-        let part3 = reader.read_le_to_u32()?;
+        let code = reader.read_le_to_u32()?;
+        let components = reader.read_le_to_u32()?;
+        let stride = reader.read_le_to_u32()?;
         reader.check_magic_number(&[0u8; 4])?;
-        let data;
-        match (part1, part2, part3) {
-            (0x05, 0x01, 0x00) => {
-                let mut strings = Vec::with_capacity(nb_entries as usize);
-                for _ in 0..nb_entries {
-                    let id = reader.read_le_to_u16()?;
-                    let name = strings_table[id as usize].clone();
-                    strings.push(name);
+        let element_type = ElementType::from_code(code);
+        // Values past `components` in an element are padding
+        let values_per_element = stride.max(components).max(1);
+        let is_padding = |value: u32| value % values_per_element >= components.max(1);
+        let data = match element_type {
+            ElementType::F32 => {
+                let mut values = Vec::with_capacity(nb_values as usize);
+                for v in 0..nb_values {
+                    let value = reader.read_le_to_f32()?;
+                    if !is_padding(v) {
+                        values.push(value);
+                    }
                 }
-                data = BufferData::BoneNames(strings);
+                BufferData::F32(values)
             }
-            (0x0C, 0x10, 0x10) => {
-                let mut matrices = Vec::with_capacity((nb_entries / 16) as usize);
-                for _ in 0..(nb_entries / 16) {
-                    let mut matrix = [0f32; 16];
-                    for i in 0..16 {
-                        matrix[i] = reader.read_le_to_f32()?;
+            ElementType::StringReference => {
+                let mut strings = Vec::with_capacity(nb_values as usize);
+                for v in 0..nb_values {
+                    let id = reader.read_le_to_u16()?;
+                    if !is_padding(v) {
+                        strings.push(strings_table[id as usize].clone());
                     }
-                    matrices.push(matrix);
                 }
-                data = BufferData::InverseBindMatrices(matrices);
+                BufferData::Strings(strings)
+            }
+            ElementType::Unknown(_) => {
+                let mut values = Vec::with_capacity(nb_values as usize);
+                for _ in 0..nb_values {
+                    values.push(reader.read_le_to_u32()?);
+                }
+                BufferData::Raw(values)
             }
-            _ => return Err(ISM2ImportError::UnrecognizedBufferType),
+        };
+        Ok(Buffer {
+            element_type,
+            components,
+            stride,
+            data,
+        })
+    }
+
+    /// Number of elements, each made of `components` values
+    pub fn len(&self) -> usize {
+        let (nb_values, values_per_element) = match self.data {
+            BufferData::F32(ref v) => (v.len(), self.components),
+            BufferData::Strings(ref v) => (v.len(), self.components),
+            // Raw values still have their padding
+            BufferData::Raw(ref v) => (v.len(), self.stride.max(self.components)),
+        };
+        nb_values / (values_per_element.max(1) as usize)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The content of this buffer if it is a list of bone names
+    pub fn bone_names(&self) -> Option<&[String]> {
+        match self.data {
+            BufferData::Strings(ref names) if self.components == 1 => Some(names),
+            _ => None,
+        }
+    }
+
+    /// The content of this buffer if it is a list of 4x4 matrices
+    pub fn inverse_bind_matrices(&self) -> Option<Vec<[f32; 16]>> {
+        match self.data {
+            BufferData::F32(ref values) if self.components == 16 => Some(
+                values
+                    .chunks_exact(16)
+                    .map(|c| {
+                        let mut matrix = [0f32; 16];
+                        matrix.copy_from_slice(c);
+                        matrix
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl ElementType {
+    pub fn from_code(code: u32) -> ElementType {
+        match code {
+            0x05 => ElementType::StringReference,
+            0x0C => ElementType::F32,
+            x => ElementType::Unknown(x),
+        }
+    }
+
+    pub fn code(self) -> u32 {
+        match self {
+            ElementType::StringReference => 0x05,
+            ElementType::F32 => 0x0C,
+            ElementType::Unknown(x) => x,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn buffer(code: u32, components: u32, stride: u32, values: &[u32]) -> Cursor<Vec<u8>> {
+        #[rustfmt::skip]
+        let mut words = vec![0x44, 0x20, values.len() as u32, 0, code, components, stride, 0];
+        words.extend_from_slice(values);
+        Cursor::new(
+            words
+                .iter()
+                .flat_map(|w| w.to_le_bytes().to_vec())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn bone_names() {
+        let strings = vec!["a".to_string(), "b".to_string()];
+        // String references are u16, the second half of the word is not read
+        let buffer = Buffer::import(&mut buffer(0x05, 1, 0, &[0x0000_0001]), &strings).unwrap();
+        assert_eq!(buffer.element_type, ElementType::StringReference);
+        assert_eq!(buffer.len(), 1);
+        assert_eq!(buffer.bone_names().unwrap(), &["b".to_string()]);
+    }
+
+    #[test]
+    fn inverse_bind_matrices() {
+        let values: Vec<u32> = (0..32).map(|v| (v as f32).to_bits()).collect();
+        let buffer = Buffer::import(&mut buffer(0x0C, 16, 16, &values), &[]).unwrap();
+        assert_eq!(buffer.len(), 2);
+        let matrices = buffer.inverse_bind_matrices().unwrap();
+        assert_eq!(matrices[1][0], 16.0);
+        assert_eq!(matrices[1][15], 31.0);
+    }
+
+    #[test]
+    fn padding() {
+        // Three values per element, then one of padding
+        let values: Vec<u32> = (0..8).map(|v| (v as f32).to_bits()).collect();
+        let buffer = Buffer::import(&mut buffer(0x0C, 3, 4, &values), &[]).unwrap();
+        assert_eq!(buffer.len(), 2);
+        match buffer.data {
+            BufferData::F32(ref v) => assert_eq!(v, &[0.0, 1.0, 2.0, 4.0, 5.0, 6.0]),
+            _ => panic!("Expected f32 values"),
+        }
+    }
+
+    #[test]
+    fn unknown_type() {
+        let buffer = Buffer::import(&mut buffer(0x01, 2, 0, &[1, 2, 3, 4]), &[]).unwrap();
+        assert_eq!(buffer.element_type, ElementType::Unknown(0x01));
+        assert_eq!(buffer.element_type.code(), 0x01);
+        assert_eq!(buffer.len(), 2);
+        assert!(buffer.bone_names().is_none());
+        assert!(buffer.inverse_bind_matrices().is_none());
+        match buffer.data {
+            BufferData::Raw(ref v) => assert_eq!(v, &[1, 2, 3, 4]),
+            _ => panic!("Expected raw values"),
         }
-        Ok(Buffer { data })
     }
 }
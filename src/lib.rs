@@ -1,21 +1,34 @@
 extern crate ez_io;
 extern crate half;
+extern crate my_gltf;
+extern crate png;
+extern crate rgb;
+extern crate tid;
 
+pub mod bounds;
 pub mod error;
+pub mod export;
 pub mod joint_definition;
 pub mod joint_extra;
+pub mod math;
 pub mod model_data;
+pub mod primitive;
+pub mod skeleton;
+mod skeleton_edit;
 pub mod string_table;
 pub mod texture_definition;
+pub mod validation;
 
 use crate::error::ISM2ImportError;
 use ez_io::{MagicNumberCheck, ReadE};
 use crate::joint_definition::JointDefinition;
 use crate::joint_extra::JointExtra;
 use crate::model_data::ModelData;
+use crate::skeleton::{BindPoseReport, Skeleton};
 use std::io::{Read, Seek, SeekFrom};
 use crate::string_table::import_strings_table;
 use crate::texture_definition::TextureDefinition;
+use crate::validation::ValidationReport;
 
 type Result<T> = std::result::Result<T, ISM2ImportError>;
 
@@ -65,10 +78,10 @@ impl ISM2 {
                             reader.seek(SeekFrom::Start(u64::from(o.offset)))?;
                             import_strings_table(reader)?
                         }
-                        _ => panic!(), // TODO Create relevant error
+                        _ => return Err(ISM2ImportError::NoStringTable),
                     }
                 }
-                None => panic!(), // TODO Create relevant error
+                None => return Err(ISM2ImportError::NoStringTable),
             }
         };
         // Read all other sections
@@ -112,13 +125,63 @@ impl ISM2 {
             sections,
         })
     }
+
+    /// Builds the skeleton from the first Joint Definition section, if any
+    pub fn skeleton(&self) -> Option<Skeleton<'_>> {
+        self.sections.iter().find_map(|s| match s {
+            Section::JointDefinition(jd) => Some(Skeleton::new(jd)),
+            _ => None,
+        })
+    }
+
+    /// Compares the skeleton with the inverse bind matrices of the first Joint Extra section.
+    /// Returns `None` if the file has no skeleton or no Joint Extra section.
+    pub fn check_bind_pose(&self, tolerance: f32) -> Option<BindPoseReport> {
+        let skeleton = self.skeleton()?;
+        let joint_extra = self.sections.iter().find_map(|s| match s {
+            Section::JointExtra(je) => Some(je),
+            _ => None,
+        })?;
+        let matrices = skeleton.inverse_bind_matrices_from(joint_extra);
+        Some(skeleton.check_inverse_bind_matrices(&matrices, tolerance))
+    }
+
+    /// Validates the geometry of every Model Data section against the skeleton of the file
+    pub fn validate(&self) -> ValidationReport {
+        let skeleton = self.skeleton();
+        let mut report = ValidationReport::default();
+        for section in &self.sections {
+            if let Section::ModelData(md) = section {
+                report.append(&mut md.validate(skeleton.as_ref()));
+            }
+        }
+        report
+    }
 }
 
 impl SectionInfo {
     fn import<R: Read>(reader: &mut R) -> Result<SectionInfo> {
         Ok(SectionInfo {
-            magic_number: reader.read_le_to_u32().unwrap(),
-            offset: reader.read_le_to_u32().unwrap(),
+            magic_number: reader.read_le_to_u32()?,
+            offset: reader.read_le_to_u32()?,
         })
     }
 }
+
+/// Reads a sub-section made of its magic number, its size in bytes and a payload that is not understood.
+/// Returns the payload as words, then the trailing bytes when the size is not a multiple of 4.
+/// It is read word by word, so that a wrong size fails at the end of the file rather than allocating that much.
+pub(crate) fn read_raw_payload<R: Read>(
+    reader: &mut R,
+    magic_number: u32,
+) -> Result<(Vec<u32>, Vec<u8>)> {
+    reader.check_magic_number(&magic_number.to_le_bytes())?;
+    let size = reader.read_le_to_u32()?.saturating_sub(8);
+    let mut words = Vec::new();
+    for _ in 0..size / 4 {
+        words.push(reader.read_le_to_u32()?);
+    }
+    let mut trailing = vec![0u8; (size % 4) as usize];
+    reader.read_exact(&mut trailing)?;
+    Ok((words, trailing))
+}
@@ -0,0 +1,411 @@
+/// 4x4 matrix stored row by row, like the ones found in ISM2 files.
+/// Matrices transform column vectors, so the translation lives in elements 3, 7 and 11.
+pub type Matrix4 = [f32; 16];
+
+pub const IDENTITY: Matrix4 = [
+    1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+];
+
+/// Order in which the three Euler rotations are applied, Maya style: `Xyz` rotates around X first and Z last
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RotationOrder {
+    #[default]
+    Xyz,
+    Yzx,
+    Zxy,
+    Xzy,
+    Yxz,
+    Zyx,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl RotationOrder {
+    /// Axes in the order they are applied, 0 being X
+    pub fn axes(self) -> [usize; 3] {
+        match self {
+            RotationOrder::Xyz => [0, 1, 2],
+            RotationOrder::Yzx => [1, 2, 0],
+            RotationOrder::Zxy => [2, 0, 1],
+            RotationOrder::Xzy => [0, 2, 1],
+            RotationOrder::Yxz => [1, 0, 2],
+            RotationOrder::Zyx => [2, 1, 0],
+        }
+    }
+
+    /// Order applying the axes in the given order, None if an axis is missing or repeated
+    pub fn from_axes(axes: [usize; 3]) -> Option<RotationOrder> {
+        Some(match axes {
+            [0, 1, 2] => RotationOrder::Xyz,
+            [1, 2, 0] => RotationOrder::Yzx,
+            [2, 0, 1] => RotationOrder::Zxy,
+            [0, 2, 1] => RotationOrder::Xzy,
+            [1, 0, 2] => RotationOrder::Yxz,
+            [2, 1, 0] => RotationOrder::Zyx,
+            _ => return None,
+        })
+    }
+}
+
+impl Quaternion {
+    pub fn identity() -> Quaternion {
+        Quaternion {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        }
+    }
+
+    /// Extracts the rotation of a matrix that has no scale or shear
+    pub fn from_matrix(m: &Matrix4) -> Quaternion {
+        let trace = m[0] + m[5] + m[10];
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion {
+                w: 0.25 * s,
+                x: (m[9] - m[6]) / s,
+                y: (m[2] - m[8]) / s,
+                z: (m[4] - m[1]) / s,
+            }
+        } else if m[0] > m[5] && m[0] > m[10] {
+            let s = (1.0 + m[0] - m[5] - m[10]).sqrt() * 2.0;
+            Quaternion {
+                w: (m[9] - m[6]) / s,
+                x: 0.25 * s,
+                y: (m[1] + m[4]) / s,
+                z: (m[2] + m[8]) / s,
+            }
+        } else if m[5] > m[10] {
+            let s = (1.0 + m[5] - m[0] - m[10]).sqrt() * 2.0;
+            Quaternion {
+                w: (m[2] - m[8]) / s,
+                x: (m[1] + m[4]) / s,
+                y: 0.25 * s,
+                z: (m[6] + m[9]) / s,
+            }
+        } else {
+            let s = (1.0 + m[10] - m[0] - m[5]).sqrt() * 2.0;
+            Quaternion {
+                w: (m[4] - m[1]) / s,
+                x: (m[2] + m[8]) / s,
+                y: (m[6] + m[9]) / s,
+                z: 0.25 * s,
+            }
+        };
+        q.normalized()
+    }
+
+    pub fn normalized(self) -> Quaternion {
+        let length = (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).sqrt();
+        if length.is_normal() {
+            Quaternion {
+                x: self.x / length,
+                y: self.y / length,
+                z: self.z / length,
+                w: self.w / length,
+            }
+        } else {
+            Quaternion::identity()
+        }
+    }
+
+    pub fn to_matrix(self) -> Matrix4 {
+        let Quaternion { x, y, z, w } = self;
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+            0.0,
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+            0.0,
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+        ]
+    }
+}
+
+/// Returns `a * b`, which applies `b` first when transforming a vector
+pub fn multiply(a: &Matrix4, b: &Matrix4) -> Matrix4 {
+    let mut result = [0f32; 16];
+    for row in 0..4 {
+        for column in 0..4 {
+            result[row * 4 + column] = (0..4).map(|k| a[row * 4 + k] * b[k * 4 + column]).sum();
+        }
+    }
+    result
+}
+
+pub fn transpose(m: &Matrix4) -> Matrix4 {
+    let mut result = [0f32; 16];
+    for row in 0..4 {
+        for column in 0..4 {
+            result[column * 4 + row] = m[row * 4 + column];
+        }
+    }
+    result
+}
+
+pub fn translation(t: [f32; 3]) -> Matrix4 {
+    let mut m = IDENTITY;
+    m[3] = t[0];
+    m[7] = t[1];
+    m[11] = t[2];
+    m
+}
+
+pub fn scale(s: [f32; 3]) -> Matrix4 {
+    let mut m = IDENTITY;
+    m[0] = s[0];
+    m[5] = s[1];
+    m[10] = s[2];
+    m
+}
+
+/// Rotation around a single axis (0 for X, 1 for Y, 2 for Z), angle in radians
+pub fn axis_rotation(axis: usize, angle: f32) -> Matrix4 {
+    let (s, c) = angle.sin_cos();
+    let mut m = IDENTITY;
+    let (a, b) = match axis {
+        0 => (1, 2),
+        1 => (2, 0),
+        _ => (0, 1),
+    };
+    m[a * 4 + a] = c;
+    m[a * 4 + b] = -s;
+    m[b * 4 + a] = s;
+    m[b * 4 + b] = c;
+    m
+}
+
+/// Rotation matrix from Euler angles in radians
+pub fn euler_rotation(angles: [f32; 3], order: RotationOrder) -> Matrix4 {
+    order.axes().iter().fold(IDENTITY, |m, &axis| {
+        multiply(&axis_rotation(axis, angles[axis]), &m)
+    })
+}
+
+/// Euler angles in radians of a rotation matrix, the inverse of `euler_rotation`
+pub fn euler_angles(m: &Matrix4, order: RotationOrder) -> [f32; 3] {
+    let [i, j, k] = order.axes();
+    // Rotations whose axes follow X, Y, Z cyclically flip the sign of the off-diagonal terms
+    let sign = if j == (i + 1) % 3 { 1.0 } else { -1.0 };
+    let at = |row: usize, column: usize| m[row * 4 + column];
+    let mut angles = [0f32; 3];
+    let sin_middle = (-sign * at(k, i)).clamp(-1.0, 1.0);
+    angles[j] = sin_middle.asin();
+    if sin_middle.abs() < 0.999_999 {
+        angles[i] = (sign * at(k, j)).atan2(at(k, k));
+        angles[k] = (sign * at(j, i)).atan2(at(i, i));
+    } else {
+        // Gimbal lock, the first and last axes are the same so all the rotation goes to the first one
+        angles[i] = (-sign * at(j, k)).atan2(at(j, j));
+    }
+    angles
+}
+
+/// Splits a matrix into translation, rotation and scale, assuming it has no shear
+pub fn decompose(m: &Matrix4) -> ([f32; 3], Quaternion, [f32; 3]) {
+    let translation = [m[3], m[7], m[11]];
+    let mut scale = [0f32; 3];
+    for (column, s) in scale.iter_mut().enumerate() {
+        *s = (m[column].powi(2) + m[4 + column].powi(2) + m[8 + column].powi(2)).sqrt();
+    }
+    let mut rotation = IDENTITY;
+    for row in 0..3 {
+        for column in 0..3 {
+            rotation[row * 4 + column] = if scale[column].is_normal() {
+                m[row * 4 + column] / scale[column]
+            } else {
+                0.0
+            };
+        }
+    }
+    (translation, Quaternion::from_matrix(&rotation), scale)
+}
+
+/// General inverse, returns `None` if the matrix is singular
+pub fn invert(m: &Matrix4) -> Option<Matrix4> {
+    let mut inv = [0f32; 16];
+    inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+        + m[9] * m[7] * m[14]
+        + m[13] * m[6] * m[11]
+        - m[13] * m[7] * m[10];
+    inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+        - m[8] * m[7] * m[14]
+        - m[12] * m[6] * m[11]
+        + m[12] * m[7] * m[10];
+    inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+        + m[8] * m[7] * m[13]
+        + m[12] * m[5] * m[11]
+        - m[12] * m[7] * m[9];
+    inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+        - m[8] * m[6] * m[13]
+        - m[12] * m[5] * m[10]
+        + m[12] * m[6] * m[9];
+    inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+        - m[9] * m[3] * m[14]
+        - m[13] * m[2] * m[11]
+        + m[13] * m[3] * m[10];
+    inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+        + m[8] * m[3] * m[14]
+        + m[12] * m[2] * m[11]
+        - m[12] * m[3] * m[10];
+    inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+        - m[8] * m[3] * m[13]
+        - m[12] * m[1] * m[11]
+        + m[12] * m[3] * m[9];
+    inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+        + m[8] * m[2] * m[13]
+        + m[12] * m[1] * m[10]
+        - m[12] * m[2] * m[9];
+    inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+        + m[5] * m[3] * m[14]
+        + m[13] * m[2] * m[7]
+        - m[13] * m[3] * m[6];
+    inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+        - m[4] * m[3] * m[14]
+        - m[12] * m[2] * m[7]
+        + m[12] * m[3] * m[6];
+    inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+        + m[4] * m[3] * m[13]
+        + m[12] * m[1] * m[7]
+        - m[12] * m[3] * m[5];
+    inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+        - m[4] * m[2] * m[13]
+        - m[12] * m[1] * m[6]
+        + m[12] * m[2] * m[5];
+    inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+        - m[5] * m[3] * m[10]
+        - m[9] * m[2] * m[7]
+        + m[9] * m[3] * m[6];
+    inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+        + m[4] * m[3] * m[10]
+        + m[8] * m[2] * m[7]
+        - m[8] * m[3] * m[6];
+    inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+        - m[4] * m[3] * m[9]
+        - m[8] * m[1] * m[7]
+        + m[8] * m[3] * m[5];
+    inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+        + m[4] * m[2] * m[9]
+        + m[8] * m[1] * m[6]
+        - m[8] * m[2] * m[5];
+    let det = m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12];
+    if det == 0.0 || !det.is_finite() {
+        return None;
+    }
+    for v in inv.iter_mut() {
+        *v /= det;
+    }
+    Some(inv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    const ORDERS: [RotationOrder; 6] = [
+        RotationOrder::Xyz,
+        RotationOrder::Yzx,
+        RotationOrder::Zxy,
+        RotationOrder::Xzy,
+        RotationOrder::Yxz,
+        RotationOrder::Zyx,
+    ];
+
+    fn assert_close(a: &Matrix4, b: &Matrix4) {
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn euler_angles_roundtrip() {
+        for &order in ORDERS.iter() {
+            assert_eq!(RotationOrder::from_axes(order.axes()), Some(order));
+            // Unique as long as every angle is within a quarter turn
+            let angles = [0.3, -1.1, 0.7];
+            let m = euler_rotation(angles, order);
+            let back = euler_angles(&m, order);
+            for (a, b) in angles.iter().zip(back.iter()) {
+                assert!(
+                    (a - b).abs() < 1e-4,
+                    "{:?}: {:?} != {:?}",
+                    order,
+                    angles,
+                    back
+                );
+            }
+            assert_close(&euler_rotation(back, order), &m);
+        }
+    }
+
+    #[test]
+    fn euler_angles_gimbal_lock() {
+        for &order in ORDERS.iter() {
+            let mut angles = [0.4, 0.4, 0.4];
+            angles[order.axes()[1]] = FRAC_PI_2;
+            let m = euler_rotation(angles, order);
+            // The angles differ, the rotation does not
+            assert_close(&euler_rotation(euler_angles(&m, order), order), &m);
+        }
+    }
+
+    #[test]
+    fn quaternion_from_matrix() {
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        let q = Quaternion::from_matrix(&axis_rotation(2, FRAC_PI_2));
+        assert!((q.z - half).abs() < 1e-6 && (q.w - half).abs() < 1e-6);
+        assert!(q.x.abs() < 1e-6 && q.y.abs() < 1e-6);
+        // Half turns have a zero trace, which goes through the other branches
+        let matrices = [
+            euler_rotation([0.3, -1.1, 2.5], RotationOrder::Xyz),
+            axis_rotation(0, PI),
+            axis_rotation(1, PI),
+            axis_rotation(2, PI),
+        ];
+        for m in matrices.iter() {
+            assert_close(&Quaternion::from_matrix(m).to_matrix(), m);
+        }
+    }
+
+    #[test]
+    fn invert_matrix() {
+        let m = multiply(
+            &multiply(
+                &translation([1.0, -2.0, 3.0]),
+                &euler_rotation([0.3, -1.1, 2.5], RotationOrder::Zxy),
+            ),
+            &scale([2.0, 0.5, 1.5]),
+        );
+        let inverse = invert(&m).unwrap();
+        assert_close(&multiply(&m, &inverse), &IDENTITY);
+        assert_close(&multiply(&inverse, &m), &IDENTITY);
+        let (t, r, s) = decompose(&m);
+        assert_eq!(t, [1.0, -2.0, 3.0]);
+        assert!(
+            (s[0] - 2.0).abs() < 1e-5 && (s[1] - 0.5).abs() < 1e-5 && (s[2] - 1.5).abs() < 1e-5
+        );
+        assert_close(
+            &r.to_matrix(),
+            &euler_rotation([0.3, -1.1, 2.5], RotationOrder::Zxy),
+        );
+        assert!(invert(&scale([1.0, 0.0, 1.0])).is_none());
+    }
+}
@@ -2,11 +2,12 @@ use crate::error::{ISM2ImportError, UnknownSubSection};
 use ez_io::{MagicNumberCheck, ReadE};
 use half::f16;
 use std::io::{Read, Seek, SeekFrom};
-use crate::Result;
+use crate::{read_raw_payload, Result};
 
 /// Defines all the geometry of the model
 pub struct ModelData {
-    pub zero_a: Unnamed0A,
+    /// Each group looks like a separate object of the model
+    pub groups: Vec<Unnamed0A>,
 }
 
 pub struct Unnamed0A {
@@ -16,7 +17,7 @@ pub struct Unnamed0A {
 pub enum SubSection {
     Vertices(Vertices),
     Mesh(Mesh),
-    Unnamed6E,
+    Unnamed6E(Unnamed6E),
 }
 
 pub struct Vertices {
@@ -28,17 +29,64 @@ pub struct Vertices {
 #[derive(Clone)]
 pub struct VertexAttribute {
     pub attribute_type: u32,
+    /// Looks like the offset of the values inside a vertex, in bytes from `buffer_offset`
     pub unknown2: u32,
+    /// Looks like the format of the values, see `ValueFormat`
     pub unknown3: u32,
+    /// Looks like the number of values per vertex
     pub unknown4: u32,
+    /// Looks like the number of bytes between two vertices
     pub unknown5: u32,
     pub buffer_offset: u32,
 }
 
+/// What a vertex attribute holds, from its `attribute_type`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeSemantic {
+    /// 0x00
+    Position,
+    /// 0x01
+    Weights,
+    /// 0x02, the U texture coordinate is stored in the 4th value
+    Normal,
+    /// 0x03, the V texture coordinate is stored in the 4th value
+    Tangent,
+    /// 0x07
+    JointIndices,
+    /// 0x08
+    Unknown08,
+    /// 0x0E, looks like RGBA colors
+    Color,
+    Other(u32),
+}
+
+/// Format of the values of a vertex attribute.
+/// The codes are guessed from Joint Extra buffers, where 0x0C is f32.
+/// Attributes with any other code are read with their legacy layout, see `AttributeSemantic::legacy_layout`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueFormat {
+    U8,
+    U16,
+    U32,
+    F16,
+    F32,
+}
+
+/// Where and how to read an attribute for every vertex
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AttributeLayout {
+    pub semantic: AttributeSemantic,
+    pub format: ValueFormat,
+    pub components: u32,
+    /// Absolute offset of the values of the first vertex
+    pub offset: u64,
+    pub stride: u32,
+}
+
 pub enum VerticesDataBuffer {
     Geometry(VerticesGeometryBuffer),
     Rigging(VerticesRiggingBuffer),
-    Unknown08,
+    Unknown08(VerticesUnknown08Buffer),
 }
 
 pub struct VerticesGeometryBuffer {
@@ -49,6 +97,14 @@ pub struct VerticesRiggingBuffer {
     pub vertices: Vec<VertexRigging>,
 }
 
+/// Data of a 0x08 attribute. Its meaning is unknown, it could be vertex colors or a second set of UVs or normals.
+pub struct VerticesUnknown08Buffer {
+    /// Values of every vertex, read with the layout of the attribute
+    pub values: Vec<[f32; 4]>,
+    /// Bytes of every vertex as found in the file
+    pub raw: Vec<Vec<u8>>,
+}
+
 pub struct Vector3D<T> {
     // Move to Utils
     pub x: T,
@@ -64,13 +120,42 @@ pub struct Vector2D<T> {
 
 pub struct VertexGeometry {
     pub position_coordinates: Vector3D<f32>,
+    /// Stored with the normal and the tangent, which are f16 in the legacy layout
     pub texture_coordinates: Vector2D<f16>,
+    /// Further UV sets, like lightmap UVs, in attribute order. Kept as f32 so that f32 attributes lose no precision.
+    pub extra_texture_coordinates: Vec<Vector2D<f32>>,
     pub frenet_frame: FrenetFrame<f16>,
+    /// RGBA between 0 and 1
+    pub color: Option<[f32; 4]>,
 }
 
+/// A vertex with every attribute converted to f32, see `VertexGeometry::decode`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    /// Always of unit length
+    pub normal: [f32; 3],
+    /// Unit length and perpendicular to the normal, handedness in the 4th value like in glTF
+    pub tangent: [f32; 4],
+    pub texture_coordinates: [f32; 2],
+    pub extra_texture_coordinates: Vec<[f32; 2]>,
+    pub color: Option<[f32; 4]>,
+    /// The stored normal was zero or not finite and got replaced
+    pub degenerate_normal: bool,
+    /// The stored tangent was zero, not finite or parallel to the normal and got replaced
+    pub degenerate_tangent: bool,
+}
+
+/// Most joints a single vertex can be bound to
+pub const MAX_INFLUENCES: usize = 8;
+
+/// Joints influencing a vertex, slots past `nb_influences` are always 0
 pub struct VertexRigging {
-    pub joints: (u8, u8, u8, u8),
-    pub weights: (f32, f32, f32, f32),
+    /// In-vertex IDs of the joints, see `Joint::in_vertex_id`
+    pub joints: [u16; MAX_INFLUENCES],
+    pub weights: [f32; MAX_INFLUENCES],
+    /// Number of slots stored in the file
+    pub nb_influences: usize,
 }
 
 pub struct FrenetFrame<T> {
@@ -80,30 +165,72 @@ pub struct FrenetFrame<T> {
 }
 
 pub struct Mesh {
+    /// Header words between the number of sub-sections and the number of faces
+    pub unknown: [u32; 3],
+    /// Number of triangles, a triangle list has 3 indices per triangle and a triangle strip 2 more indices than triangles
     pub nb_faces: u32,
     pub sub_sections: Vec<MeshSubSection>,
 }
 
 pub enum MeshSubSection {
     Faces(Faces),
-    Unnamed6E,
+    Unnamed6E(Unnamed6E),
+}
+
+/// Found next to meshes. None of its fields is understood yet, the payload is kept as found in the file.
+pub struct Unnamed6E {
+    /// Everything after the size field, read as words
+    pub unknown: Vec<u32>,
+    /// Trailing bytes when the size is not a multiple of 4
+    pub raw: Vec<u8>,
 }
 
 pub struct Faces {
-    pub faces: Vec<Face>,
+    /// Header words after the header size, the first two are read as `index_format` and `topology`
+    pub header: [u32; 3],
+    pub index_format: IndexFormat,
+    pub topology: Topology,
+    pub indices: Vec<u32>,
+}
+
+/// Width of the indices, read from the first word of the faces header with the codes of `ValueFormat`:
+/// 0x02 for 16-bit and 0x03 for 32-bit. They have not been checked against sample files,
+/// any other code is read as 16-bit like before the header was decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexFormat {
+    U16,
+    U32,
+}
+
+/// How indices make up triangles, read from the second word of the faces header.
+/// The codes are assumed to follow the OpenGL and Direct3D numbering, 0x04 for lists and 0x05 for strips.
+/// They have not been checked against sample files, any other code is read as a list like before the header was decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Topology {
+    TriangleList,
+    TriangleStrip,
 }
 
+/// A triangle, see `Faces::triangles`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Face {
-    pub points: (u16, u16, u16),
+    pub points: (u32, u32, u32),
 }
 
 impl ModelData {
     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<ModelData> {
-        reader.check_magic_number(&[0xB, 0, 0, 0, 0xC, 0, 0, 0, 0x1, 0, 0, 0])?; // Magic Number, 0x0C, Number of sections should be 1
-        let section_offset = reader.read_le_to_u32()?;
-        reader.seek(SeekFrom::Start(u64::from(section_offset)))?;
-        let zero_a = Unnamed0A::import(reader)?;
-        Ok(ModelData { zero_a })
+        reader.check_magic_number(&[0xB, 0, 0, 0, 0xC, 0, 0, 0])?; // Magic Number, 0x0C
+        let nb_groups = reader.read_le_to_u32()?;
+        let mut offsets = Vec::with_capacity(nb_groups as usize);
+        for _ in 0..nb_groups {
+            offsets.push(reader.read_le_to_u32()?);
+        }
+        let mut groups = Vec::with_capacity(nb_groups as usize);
+        for o in offsets {
+            reader.seek(SeekFrom::Start(u64::from(o)))?;
+            groups.push(Unnamed0A::import(reader)?);
+        }
+        Ok(ModelData { groups })
     }
 }
 
@@ -123,6 +250,17 @@ impl Unnamed0A {
         }
         Ok(Unnamed0A { sub_sections })
     }
+
+    /// Unnamed6E sub-sections found directly in this section, not in meshes
+    pub fn unnamed_6e(&self) -> Vec<&Unnamed6E> {
+        self.sub_sections
+            .iter()
+            .filter_map(|s| match s {
+                SubSection::Unnamed6E(u) => Some(u),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 impl SubSection {
@@ -132,7 +270,7 @@ impl SubSection {
         Ok(match magic_number {
             0x59 => SubSection::Vertices(Vertices::import(reader)?),
             0x46 => SubSection::Mesh(Mesh::import(reader)?),
-            0x6E => SubSection::Unnamed6E,
+            0x6E => SubSection::Unnamed6E(Unnamed6E::import(reader)?),
             x => {
                 return Err(ISM2ImportError::UnknownSubSection(UnknownSubSection {
                     in_section: 0x0A,
@@ -159,28 +297,43 @@ impl Vertices {
             reader.seek(SeekFrom::Start(u64::from(o)))?;
             attributes.push(VertexAttribute::import(reader)?);
         }
-        let just_for_you_special_snowflake = attributes.clone();
-        let any_attribute = match just_for_you_special_snowflake.get(0) {
-            Some(a) => a,
-            None => return Err(ISM2ImportError::NoAttributes),
-        };
-        reader.seek(SeekFrom::Start(u64::from(any_attribute.buffer_offset)))?;
-        let vertices_data_buffer = match any_attribute.attribute_type {
-            0x00 | 0x02 | 0x0E | 0x03 => {
-                VerticesDataBuffer::Geometry(VerticesGeometryBuffer::import(reader, nb_vertices)?)
-            }
-            0x07 | 0x01 => {
-                VerticesDataBuffer::Rigging(VerticesRiggingBuffer::import(reader, nb_vertices)?)
-            }
-            0x08 => {
-                VerticesDataBuffer::Unknown08
-            }
-            x => {
-                return Err(ISM2ImportError::UnknownSubSection(UnknownSubSection {
-                    in_section: 0x59,
-                    failed_to_match: x,
-                }))
+        let layouts = attribute_layouts(&attributes);
+        let kind = buffer_kind(&layouts)?;
+        // Read everything at once, attributes are usually interleaved
+        let start = layouts.iter().map(|l| l.offset).min().unwrap_or(0);
+        let end = layouts
+            .iter()
+            .map(|l| l.end(nb_vertices))
+            .max()
+            .unwrap_or(start);
+        // The sizes come from the file, make sure they do not go past its end before allocating anything
+        if end > reader.seek(SeekFrom::End(0))? {
+            return Err(ISM2ImportError::IO(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "Vertex data goes past the end of the file",
+            )));
+        }
+        reader.seek(SeekFrom::Start(start))?;
+        let mut data = vec![0u8; (end - start) as usize];
+        reader.read_exact(&mut data)?;
+        let vertices_data_buffer = match kind {
+            AttributeSemantic::Weights | AttributeSemantic::JointIndices => {
+                VerticesDataBuffer::Rigging(VerticesRiggingBuffer::decode(
+                    &layouts,
+                    &data,
+                    start,
+                    nb_vertices,
+                ))
             }
+            AttributeSemantic::Unknown08 => VerticesDataBuffer::Unknown08(
+                VerticesUnknown08Buffer::decode(&layouts, &data, start, nb_vertices),
+            ),
+            _ => VerticesDataBuffer::Geometry(VerticesGeometryBuffer::decode(
+                &layouts,
+                &data,
+                start,
+                nb_vertices,
+            )),
         };
         Ok(Vertices {
             nb_vertices,
@@ -188,6 +341,95 @@ impl Vertices {
             buffer: vertices_data_buffer,
         })
     }
+
+    pub fn unknown_08(&self) -> Option<&VerticesUnknown08Buffer> {
+        match self.buffer {
+            VerticesDataBuffer::Unknown08(ref b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Layouts used to decode the attributes, in the same order
+    pub fn layouts(&self) -> Vec<AttributeLayout> {
+        attribute_layouts(&self.attributes)
+    }
+}
+
+/// Most values an attribute can have per vertex, reached by joint indices and weights of 8 influences
+const MAX_COMPONENTS: u32 = 8;
+
+/// Number of bytes per vertex of the layout used before attributes were decoded, for both geometry and rigging
+const LEGACY_STRIDE: u32 = 0x20;
+
+/// Every attribute is read at `buffer_offset + unknown2` with the declared format, number of values and stride.
+/// When the format is not known, or the values do not fit in the stride, the legacy layout of the attribute is used instead.
+fn attribute_layouts(attributes: &[VertexAttribute]) -> Vec<AttributeLayout> {
+    attributes
+        .iter()
+        .map(|attribute| {
+            let semantic = attribute.semantic();
+            let components = attribute.unknown4;
+            let declared = ValueFormat::from_code(attribute.unknown3).filter(|format| {
+                let size = u64::from(format.size()) * u64::from(components);
+                components != 0
+                    && components <= MAX_COMPONENTS
+                    && u64::from(attribute.unknown2) + size <= u64::from(attribute.unknown5)
+            });
+            match declared {
+                Some(format) => AttributeLayout {
+                    semantic,
+                    format,
+                    components,
+                    offset: u64::from(attribute.buffer_offset) + u64::from(attribute.unknown2),
+                    stride: attribute.unknown5,
+                },
+                None => {
+                    let (format, components, offset) = semantic.legacy_layout();
+                    AttributeLayout {
+                        semantic,
+                        format,
+                        components,
+                        offset: u64::from(attribute.buffer_offset) + u64::from(offset),
+                        stride: LEGACY_STRIDE,
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// Semantic deciding what buffer the attributes are decoded into, taking all of them into account.
+/// Attributes of unknown semantic are only allowed next to geometry, where they can be extra UV sets.
+fn buffer_kind(layouts: &[AttributeLayout]) -> Result<AttributeSemantic> {
+    let semantics: Vec<AttributeSemantic> = layouts.iter().map(|l| l.semantic).collect();
+    let is_rigging = |s: &AttributeSemantic| {
+        *s == AttributeSemantic::Weights || *s == AttributeSemantic::JointIndices
+    };
+    let is_geometry = |s: &AttributeSemantic| {
+        matches!(
+            s,
+            AttributeSemantic::Position
+                | AttributeSemantic::Normal
+                | AttributeSemantic::Tangent
+                | AttributeSemantic::Color
+        )
+    };
+    let rigging = semantics.iter().find(|s| is_rigging(s));
+    let geometry = semantics.iter().find(|s| is_geometry(s));
+    let other = semantics.iter().find(|s| !is_rigging(s) && !is_geometry(s));
+    match (geometry, rigging, other) {
+        (None, None, None) => Err(ISM2ImportError::NoAttributes),
+        (Some(s), None, _) => Ok(*s),
+        (None, Some(s), None) => Ok(*s),
+        (Some(_), Some(s), _) | (None, Some(_), Some(s)) => {
+            Err(ISM2ImportError::InvalidVertexAttribute(s.code()))
+        }
+        (None, None, Some(AttributeSemantic::Unknown08)) => Ok(AttributeSemantic::Unknown08),
+        (None, None, Some(s)) => Err(ISM2ImportError::UnknownSubSection(UnknownSubSection {
+            in_section: 0x59,
+            failed_to_match: s.code(),
+        })),
+    }
 }
 
 impl VertexAttribute {
@@ -201,96 +443,413 @@ impl VertexAttribute {
             buffer_offset: reader.read_le_to_u32()?,
         })
     }
+
+    pub fn semantic(&self) -> AttributeSemantic {
+        match self.attribute_type {
+            0x00 => AttributeSemantic::Position,
+            0x01 => AttributeSemantic::Weights,
+            0x02 => AttributeSemantic::Normal,
+            0x03 => AttributeSemantic::Tangent,
+            0x07 => AttributeSemantic::JointIndices,
+            0x08 => AttributeSemantic::Unknown08,
+            0x0E => AttributeSemantic::Color,
+            x => AttributeSemantic::Other(x),
+        }
+    }
+}
+
+impl AttributeSemantic {
+    /// Format, number of values and offset inside a vertex of this attribute in the legacy layout
+    pub fn legacy_layout(self) -> (ValueFormat, u32, u32) {
+        match self {
+            AttributeSemantic::Position => (ValueFormat::F32, 3, 0x00),
+            AttributeSemantic::Normal => (ValueFormat::F16, 4, 0x0C),
+            AttributeSemantic::Tangent => (ValueFormat::F16, 4, 0x14),
+            AttributeSemantic::Color => (ValueFormat::U8, 4, 0x1C),
+            AttributeSemantic::JointIndices => (ValueFormat::U8, 4, 0x00),
+            AttributeSemantic::Weights => (ValueFormat::F32, 4, 0x04),
+            _ => (ValueFormat::U8, 4, 0x00),
+        }
+    }
+
+    /// The `attribute_type` this semantic comes from
+    pub fn code(self) -> u32 {
+        match self {
+            AttributeSemantic::Position => 0x00,
+            AttributeSemantic::Weights => 0x01,
+            AttributeSemantic::Normal => 0x02,
+            AttributeSemantic::Tangent => 0x03,
+            AttributeSemantic::JointIndices => 0x07,
+            AttributeSemantic::Unknown08 => 0x08,
+            AttributeSemantic::Color => 0x0E,
+            AttributeSemantic::Other(x) => x,
+        }
+    }
+}
+
+impl ValueFormat {
+    pub fn from_code(code: u32) -> Option<ValueFormat> {
+        match code {
+            0x01 => Some(ValueFormat::U8),
+            0x02 => Some(ValueFormat::U16),
+            0x03 => Some(ValueFormat::U32),
+            0x0B => Some(ValueFormat::F16),
+            0x0C => Some(ValueFormat::F32),
+            _ => None,
+        }
+    }
+
+    /// Factor bringing integer values between 0 and 1, floats are left as they are
+    pub fn normalization_scale(self) -> f32 {
+        match self {
+            ValueFormat::U8 => 1.0 / 255.0,
+            ValueFormat::U16 => 1.0 / 65_535.0,
+            ValueFormat::U32 => 1.0 / 4_294_967_295.0,
+            ValueFormat::F16 | ValueFormat::F32 => 1.0,
+        }
+    }
+
+    /// Size of a single value in bytes
+    pub fn size(self) -> u32 {
+        match self {
+            ValueFormat::U8 => 1,
+            ValueFormat::U16 | ValueFormat::F16 => 2,
+            ValueFormat::U32 | ValueFormat::F32 => 4,
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> f32 {
+        match self {
+            ValueFormat::U8 => f32::from(bytes[0]),
+            ValueFormat::U16 => f32::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+            ValueFormat::U32 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32,
+            ValueFormat::F16 => f16::from_bits(u16::from_le_bytes([bytes[0], bytes[1]])).to_f32(),
+            ValueFormat::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+}
+
+impl AttributeLayout {
+    pub fn element_size(&self) -> u32 {
+        self.format.size() * self.components
+    }
+
+    /// Offset right after the values of the last vertex
+    pub fn end(&self, nb_vertices: u32) -> u64 {
+        match nb_vertices {
+            0 => self.offset,
+            n => {
+                self.offset
+                    + u64::from(n - 1) * u64::from(self.stride)
+                    + u64::from(self.element_size())
+            }
+        }
+    }
+
+    /// Bytes of the values of every vertex
+    pub fn raw(&self, data: &[u8], data_offset: u64, nb_vertices: u32) -> Vec<Vec<u8>> {
+        let size = self.element_size() as usize;
+        (0..nb_vertices as usize)
+            .map(|i| {
+                let start = (self.offset - data_offset) as usize + i * self.stride as usize;
+                data[start..start + size].to_vec()
+            })
+            .collect()
+    }
+
+    /// The first 4 values of every vertex, missing components are 0.
+    /// `data` holds the file starting at `data_offset` and must cover the whole attribute.
+    pub fn decode(&self, data: &[u8], data_offset: u64, nb_vertices: u32) -> Vec<[f32; 4]> {
+        let components = self.components as usize;
+        self.decode_all(data, data_offset, nb_vertices)
+            .chunks(components.max(1))
+            .map(|c| {
+                let mut value = [0f32; 4];
+                for (v, c) in value.iter_mut().zip(c.iter()) {
+                    *v = *c;
+                }
+                value
+            })
+            .collect()
+    }
+
+    /// All the values of every vertex, one after the other
+    pub fn decode_all(&self, data: &[u8], data_offset: u64, nb_vertices: u32) -> Vec<f32> {
+        let size = self.format.size() as usize;
+        let components = self.components as usize;
+        let mut values = Vec::with_capacity(nb_vertices as usize * components);
+        for i in 0..nb_vertices as usize {
+            let start = (self.offset - data_offset) as usize + i * self.stride as usize;
+            for c in 0..components {
+                values.push(self.format.decode(&data[start + c * size..]));
+            }
+        }
+        values
+    }
+}
+
+fn decode_attribute(
+    layouts: &[AttributeLayout],
+    semantic: AttributeSemantic,
+    data: &[u8],
+    data_offset: u64,
+    nb_vertices: u32,
+) -> Vec<[f32; 4]> {
+    match layouts.iter().find(|l| l.semantic == semantic) {
+        Some(l) => l.decode(data, data_offset, nb_vertices),
+        None => vec![[0f32; 4]; nb_vertices as usize],
+    }
 }
 
 impl VerticesGeometryBuffer {
-    pub fn import<R: Read + Seek>(
-        reader: &mut R,
+    /// Any attribute with 2 values per vertex that is not known to be something else is read as an extra UV set
+    pub fn decode(
+        layouts: &[AttributeLayout],
+        data: &[u8],
+        data_offset: u64,
         nb_vertices: u32,
-    ) -> Result<VerticesGeometryBuffer> {
-        let mut vertices = Vec::with_capacity(nb_vertices as usize);
-        for _ in 0..nb_vertices {
-            vertices.push(VertexGeometry::import(reader)?);
-        }
-        Ok(VerticesGeometryBuffer { vertices })
+    ) -> VerticesGeometryBuffer {
+        let decode = |s| decode_attribute(layouts, s, data, data_offset, nb_vertices);
+        let positions = decode(AttributeSemantic::Position);
+        let normals = decode(AttributeSemantic::Normal);
+        let tangents = decode(AttributeSemantic::Tangent);
+        let extra_uv_sets: Vec<Vec<[f32; 4]>> = layouts
+            .iter()
+            .filter(|l| l.components == 2)
+            .filter(|l| {
+                matches!(
+                    l.semantic,
+                    AttributeSemantic::Other(_) | AttributeSemantic::Unknown08
+                )
+            })
+            .map(|l| l.decode(data, data_offset, nb_vertices))
+            .collect();
+        let colors = layouts
+            .iter()
+            .find(|l| l.semantic == AttributeSemantic::Color)
+            .map(|l| {
+                let scale = l.format.normalization_scale();
+                let components = l.components as usize;
+                l.decode(data, data_offset, nb_vertices)
+                    .into_iter()
+                    .map(|mut c| {
+                        for (i, v) in c.iter_mut().enumerate() {
+                            *v = if i < components { *v * scale } else { 1.0 };
+                        }
+                        c
+                    })
+                    .collect::<Vec<[f32; 4]>>()
+            });
+        let half = |v: f32| f16::from_f32(v);
+        let vertices = positions
+            .iter()
+            .zip(normals.iter())
+            .zip(tangents.iter())
+            .enumerate()
+            .map(|(i, ((p, n), t))| VertexGeometry {
+                position_coordinates: Vector3D {
+                    x: p[0],
+                    y: p[1],
+                    z: p[2],
+                },
+                texture_coordinates: Vector2D {
+                    u: half(n[3]),
+                    v: half(t[3]),
+                },
+                extra_texture_coordinates: extra_uv_sets
+                    .iter()
+                    .map(|set| Vector2D {
+                        u: set[i][0],
+                        v: set[i][1],
+                    })
+                    .collect(),
+                frenet_frame: FrenetFrame {
+                    normal: Vector3D {
+                        x: half(n[0]),
+                        y: half(n[1]),
+                        z: half(n[2]),
+                    },
+                    tangent: Vector3D {
+                        x: half(t[0]),
+                        y: half(t[1]),
+                        z: half(t[2]),
+                    },
+                },
+                color: colors.as_ref().map(|c| c[i]),
+            })
+            .collect();
+        VerticesGeometryBuffer { vertices }
     }
 }
 
 impl VerticesRiggingBuffer {
-    pub fn import<R: Read + Seek>(
-        reader: &mut R,
+    /// Joint indices and weights can be split over several attributes, in which case they are concatenated in order.
+    /// Integer weights are brought between 0 and 1 like colors.
+    pub fn decode(
+        layouts: &[AttributeLayout],
+        data: &[u8],
+        data_offset: u64,
         nb_vertices: u32,
-    ) -> Result<VerticesRiggingBuffer> {
-        let mut vertices = Vec::with_capacity(nb_vertices as usize);
-        for _ in 0..nb_vertices {
-            vertices.push(VertexRigging::import(reader)?);
-        }
-        Ok(VerticesRiggingBuffer { vertices })
+    ) -> VerticesRiggingBuffer {
+        let decode = |semantic| {
+            let sets: Vec<(usize, Vec<f32>)> = layouts
+                .iter()
+                .filter(|l| l.semantic == semantic)
+                .map(|l| {
+                    let mut values = l.decode_all(data, data_offset, nb_vertices);
+                    if semantic == AttributeSemantic::Weights {
+                        let scale = l.format.normalization_scale();
+                        values.iter_mut().for_each(|v| *v *= scale);
+                    }
+                    (l.components as usize, values)
+                })
+                .collect();
+            let nb_values = sets.iter().map(|(c, _)| c).sum::<usize>();
+            let mut values = Vec::with_capacity(nb_vertices as usize);
+            for i in 0..nb_vertices as usize {
+                let mut value = [0f32; MAX_INFLUENCES];
+                let all = sets.iter().flat_map(|(c, v)| v[i * c..(i + 1) * c].iter());
+                for (v, a) in value.iter_mut().zip(all) {
+                    *v = *a;
+                }
+                values.push(value);
+            }
+            (nb_values.min(MAX_INFLUENCES), values)
+        };
+        let (nb_joints, joints) = decode(AttributeSemantic::JointIndices);
+        let (nb_weights, weights) = decode(AttributeSemantic::Weights);
+        let nb_influences = nb_joints.min(nb_weights);
+        let vertices = joints
+            .iter()
+            .zip(weights.iter())
+            .map(|(j, w)| {
+                let mut rigging = VertexRigging {
+                    joints: [0; MAX_INFLUENCES],
+                    weights: [0.0; MAX_INFLUENCES],
+                    nb_influences,
+                };
+                for i in 0..nb_influences {
+                    rigging.joints[i] = j[i] as u16;
+                    rigging.weights[i] = w[i];
+                }
+                rigging
+            })
+            .collect();
+        VerticesRiggingBuffer { vertices }
     }
 }
 
-impl VertexGeometry {
-    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<VertexGeometry> {
-        let position = (
-            reader.read_le_to_f32()?,
-            reader.read_le_to_f32()?,
-            reader.read_le_to_f32()?,
-        );
-        let normal = (
-            f16::from_bits(reader.read_le_to_u16()?),
-            f16::from_bits(reader.read_le_to_u16()?),
-            f16::from_bits(reader.read_le_to_u16()?),
-        );
-        let texture_coordinate_u = f16::from_bits(reader.read_le_to_u16()?);
-        let tangent = (
-            f16::from_bits(reader.read_le_to_u16()?),
-            f16::from_bits(reader.read_le_to_u16()?),
-            f16::from_bits(reader.read_le_to_u16()?),
-        );
-        let texture_coordinate_v = f16::from_bits(reader.read_le_to_u16()?);
-        reader.seek(SeekFrom::Current(0x04))?;
-        Ok(VertexGeometry {
-            position_coordinates: Vector3D {
-                x: position.0,
-                y: position.1,
-                z: position.2,
-            },
-            texture_coordinates: Vector2D {
-                u: texture_coordinate_u,
-                v: texture_coordinate_v,
+impl VerticesUnknown08Buffer {
+    pub fn decode(
+        layouts: &[AttributeLayout],
+        data: &[u8],
+        data_offset: u64,
+        nb_vertices: u32,
+    ) -> VerticesUnknown08Buffer {
+        match layouts
+            .iter()
+            .find(|l| l.semantic == AttributeSemantic::Unknown08)
+        {
+            Some(l) => VerticesUnknown08Buffer {
+                values: l.decode(data, data_offset, nb_vertices),
+                raw: l.raw(data, data_offset, nb_vertices),
             },
-            frenet_frame: FrenetFrame {
-                normal: Vector3D {
-                    x: normal.0,
-                    y: normal.1,
-                    z: normal.2,
-                },
-                tangent: Vector3D {
-                    x: tangent.0,
-                    y: tangent.1,
-                    z: tangent.2,
-                },
+            None => VerticesUnknown08Buffer {
+                values: Vec::new(),
+                raw: Vec::new(),
             },
-        })
+        }
     }
 }
 
+impl VertexGeometry {
+    /// Converts everything to f32 and cleans up the frame.
+    ///
+    /// The normal is normalized. A zero or non-finite normal is replaced by +Z.
+    /// The tangent is made perpendicular to the normal, then normalized.
+    /// When that is not possible, an arbitrary unit vector perpendicular to the normal is used instead.
+    /// Files do not store the direction of the bitangent, so the handedness is always 1.
+    pub fn decode(&self) -> Vertex {
+        let n = &self.frenet_frame.normal;
+        let (normal, degenerate_normal) =
+            match normalize([f32::from(n.x), f32::from(n.y), f32::from(n.z)]) {
+                Some(n) => (n, false),
+                None => ([0.0, 0.0, 1.0], true),
+            };
+        let t = &self.frenet_frame.tangent;
+        let t = [f32::from(t.x), f32::from(t.y), f32::from(t.z)];
+        let along_normal = dot(t, normal);
+        let (tangent, degenerate_tangent) = match normalize([
+            t[0] - normal[0] * along_normal,
+            t[1] - normal[1] * along_normal,
+            t[2] - normal[2] * along_normal,
+        ]) {
+            Some(t) => (t, false),
+            None => (perpendicular(normal), true),
+        };
+        Vertex {
+            position: [
+                self.position_coordinates.x,
+                self.position_coordinates.y,
+                self.position_coordinates.z,
+            ],
+            normal,
+            tangent: [tangent[0], tangent[1], tangent[2], 1.0],
+            texture_coordinates: [
+                f32::from(self.texture_coordinates.u),
+                f32::from(self.texture_coordinates.v),
+            ],
+            extra_texture_coordinates: self
+                .extra_texture_coordinates
+                .iter()
+                .map(|t| [t.u, t.v])
+                .collect(),
+            color: self.color,
+            degenerate_normal,
+            degenerate_tangent,
+        }
+    }
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// `None` if the vector is too small or not finite
+fn normalize(v: [f32; 3]) -> Option<[f32; 3]> {
+    let length = dot(v, v).sqrt();
+    if length.is_normal() && length > 1e-6 {
+        Some([v[0] / length, v[1] / length, v[2] / length])
+    } else {
+        None
+    }
+}
+
+/// Any unit vector perpendicular to a unit vector
+fn perpendicular(v: [f32; 3]) -> [f32; 3] {
+    // Cross product with the axis the least aligned with v
+    let axis = if v[0].abs() < 0.9 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 1.0, 0.0]
+    };
+    let cross = [
+        v[1] * axis[2] - v[2] * axis[1],
+        v[2] * axis[0] - v[0] * axis[2],
+        v[0] * axis[1] - v[1] * axis[0],
+    ];
+    normalize(cross).unwrap_or([1.0, 0.0, 0.0])
+}
+
 impl VertexRigging {
-    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<VertexRigging> {
-        let joints = (
-            reader.read_to_u8()?,
-            reader.read_to_u8()?,
-            reader.read_to_u8()?,
-            reader.read_to_u8()?,
-        );
-        let weights = (
-            reader.read_le_to_f32()?,
-            reader.read_le_to_f32()?,
-            reader.read_le_to_f32()?,
-            reader.read_le_to_f32()?,
-        );
-        reader.seek(SeekFrom::Current(12))?;
-        Ok(VertexRigging { joints, weights })
+    /// Joint and weight of every stored slot that actually influences the vertex, that is with a finite weight above 0
+    pub fn influences(&self) -> impl Iterator<Item = (u16, f32)> + '_ {
+        self.joints
+            .iter()
+            .cloned()
+            .zip(self.weights.iter().cloned())
+            .take(self.nb_influences)
+            .filter(|(_, w)| w.is_finite() && *w > 0.0)
     }
 }
 
@@ -298,7 +857,11 @@ impl Mesh {
     pub fn import<R: Read + Seek>(reader: &mut R) -> Result<Mesh> {
         reader.check_magic_number(&[0x46, 0, 0, 0, 0x1C, 0, 0, 0])?;
         let nb_sub_sections = reader.read_le_to_u32()?;
-        reader.seek(SeekFrom::Current(0x0C))?;
+        let unknown = [
+            reader.read_le_to_u32()?,
+            reader.read_le_to_u32()?,
+            reader.read_le_to_u32()?,
+        ];
         let nb_faces = reader.read_le_to_u32()?;
         let mut offsets = Vec::with_capacity(nb_sub_sections as usize);
         for _ in 0..nb_sub_sections {
@@ -310,10 +873,18 @@ impl Mesh {
             sub_sections.push(MeshSubSection::import(reader, nb_faces)?);
         }
         Ok(Mesh {
+            unknown,
             nb_faces,
             sub_sections,
         })
     }
+
+    pub fn unnamed_6e(&self) -> Option<&Unnamed6E> {
+        self.sub_sections.iter().find_map(|s| match s {
+            MeshSubSection::Unnamed6E(u) => Some(u),
+            _ => None,
+        })
+    }
 }
 
 impl MeshSubSection {
@@ -322,7 +893,7 @@ impl MeshSubSection {
         reader.seek(SeekFrom::Current(-4))?;
         Ok(match magic_number {
             0x45 => MeshSubSection::Faces(Faces::import(reader, nb_faces)?),
-            0x6E => MeshSubSection::Unnamed6E,
+            0x6E => MeshSubSection::Unnamed6E(Unnamed6E::import(reader)?),
             x => {
                 return Err(ISM2ImportError::UnknownSubSection(UnknownSubSection {
                     in_section: 0x46,
@@ -333,26 +904,322 @@ impl MeshSubSection {
     }
 }
 
+impl Unnamed6E {
+    pub fn import<R: Read + Seek>(reader: &mut R) -> Result<Unnamed6E> {
+        let (unknown, raw) = read_raw_payload(reader, 0x6E)?;
+        Ok(Unnamed6E { unknown, raw })
+    }
+}
+
 impl Faces {
     pub fn import<R: Read + Seek>(reader: &mut R, nb_faces: u32) -> Result<Faces> {
         reader.check_magic_number(&[0x45, 0, 0, 0, 0x14, 0, 0, 0])?;
-        reader.seek(SeekFrom::Current(0x0C))?;
-        let mut faces = Vec::with_capacity(nb_faces as usize);
-        for _ in 0..nb_faces {
-            faces.push(Face::import(reader)?);
+        let header = [
+            reader.read_le_to_u32()?,
+            reader.read_le_to_u32()?,
+            reader.read_le_to_u32()?,
+        ];
+        let index_format = IndexFormat::from_code(header[0]).unwrap_or(IndexFormat::U16);
+        let topology = Topology::from_code(header[1]).unwrap_or(Topology::TriangleList);
+        let nb_indices = match topology {
+            Topology::TriangleList => u64::from(nb_faces) * 3,
+            Topology::TriangleStrip if nb_faces == 0 => 0,
+            Topology::TriangleStrip => u64::from(nb_faces) + 2,
+        };
+        let mut indices = Vec::new();
+        for _ in 0..nb_indices {
+            indices.push(match index_format {
+                IndexFormat::U16 => u32::from(reader.read_le_to_u16()?),
+                IndexFormat::U32 => reader.read_le_to_u32()?,
+            });
         }
-        Ok(Faces { faces })
+        Ok(Faces {
+            header,
+            index_format,
+            topology,
+            indices,
+        })
     }
-}
 
-impl Face {
-    pub fn import<R: Read>(reader: &mut R) -> Result<Face> {
-        Ok(Face {
-            points: (
-                reader.read_le_to_u16()?,
-                reader.read_le_to_u16()?,
-                reader.read_le_to_u16()?,
+    /// The faces as a triangle list, whatever the topology.
+    /// Strips are unrolled keeping the winding of the first triangle, triangles using the same vertex twice are dropped
+    /// since strips use them to join separate parts.
+    pub fn triangles(&self) -> Box<dyn Iterator<Item = Face> + '_> {
+        match self.topology {
+            Topology::TriangleList => Box::new(self.indices.chunks_exact(3).map(|c| Face {
+                points: (c[0], c[1], c[2]),
+            })),
+            Topology::TriangleStrip => Box::new(
+                self.indices
+                    .windows(3)
+                    .enumerate()
+                    .map(|(n, w)| Face {
+                        points: if n % 2 == 0 {
+                            (w[0], w[1], w[2])
+                        } else {
+                            (w[1], w[0], w[2])
+                        },
+                    })
+                    .filter(|f| {
+                        let (a, b, c) = f.points;
+                        a != b && b != c && a != c
+                    }),
             ),
-        })
+        }
+    }
+}
+
+impl IndexFormat {
+    pub fn from_code(code: u32) -> Option<IndexFormat> {
+        match code {
+            0x02 => Some(IndexFormat::U16),
+            0x03 => Some(IndexFormat::U32),
+            _ => None,
+        }
+    }
+}
+
+impl Topology {
+    pub fn from_code(code: u32) -> Option<Topology> {
+        match code {
+            0x04 => Some(Topology::TriangleList),
+            0x05 => Some(Topology::TriangleStrip),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Vertices sub-section whose attributes, given as type, offset, format, number of values and stride,
+    /// all have their buffer right after the header
+    fn vertices(nb_vertices: u32, attributes: &[[u32; 5]], data: &[u8]) -> Result<Vertices> {
+        let nb_attributes = attributes.len() as u32;
+        let first_attribute = 0x1C + nb_attributes * 4;
+        let buffer_offset = first_attribute + nb_attributes * 0x18;
+        let mut words = vec![0x59, 0x1C, nb_attributes, 0, nb_vertices, 0, 0];
+        words.extend((0..nb_attributes).map(|i| first_attribute + i * 0x18));
+        for attribute in attributes {
+            words.extend_from_slice(attribute);
+            words.push(buffer_offset);
+        }
+        let mut bytes: Vec<u8> = words
+            .iter()
+            .flat_map(|w| w.to_le_bytes().to_vec())
+            .collect();
+        bytes.extend_from_slice(data);
+        Vertices::import(&mut Cursor::new(bytes))
+    }
+
+    fn f32_bytes(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect()
+    }
+
+    fn f16_bytes(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|v| f16::from_f32(*v).to_bits().to_le_bytes().to_vec())
+            .collect()
+    }
+
+    fn geometry(vertices: &Vertices) -> Vec<Vertex> {
+        match vertices.buffer {
+            VerticesDataBuffer::Geometry(ref g) => g.vertices.iter().map(|v| v.decode()).collect(),
+            _ => panic!("Expected geometry"),
+        }
+    }
+
+    /// Two vertices in the 32-byte layout: position, normal and U, tangent and V, color
+    fn legacy_geometry() -> Vec<u8> {
+        let mut data = Vec::new();
+        for i in 0..2 {
+            let i = i as f32;
+            data.extend(f32_bytes(&[i + 1.0, i + 2.0, i + 3.0]));
+            data.extend(f16_bytes(&[0.0, 1.0, 0.0, 0.25]));
+            data.extend(f16_bytes(&[1.0, 0.0, 0.0, 0.75]));
+            data.extend_from_slice(&[255, 0, 0, 255]);
+        }
+        data
+    }
+
+    #[test]
+    fn legacy_layout() {
+        let data = legacy_geometry();
+        // Format codes that are not known fall back to the legacy layout
+        let legacy = vertices(
+            2,
+            &[[0x00, 0, 0, 0, 0], [0x02, 0, 0, 0, 0], [0x03, 0, 0, 0, 0]],
+            &data,
+        )
+        .unwrap();
+        let declared = vertices(
+            2,
+            &[
+                [0x00, 0x00, 0x0C, 3, 0x20],
+                [0x02, 0x0C, 0x0B, 4, 0x20],
+                [0x03, 0x14, 0x0B, 4, 0x20],
+            ],
+            &data,
+        )
+        .unwrap();
+        assert_eq!(legacy.layouts(), declared.layouts());
+        let vertices = geometry(&legacy);
+        assert_eq!(vertices, geometry(&declared));
+        assert_eq!(vertices[1].position, [2.0, 3.0, 4.0]);
+        assert_eq!(vertices[1].normal, [0.0, 1.0, 0.0]);
+        assert_eq!(vertices[1].tangent, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(vertices[1].texture_coordinates, [0.25, 0.75]);
+        assert_eq!(vertices[1].color, None);
+    }
+
+    #[test]
+    fn legacy_layout_when_values_do_not_fit() {
+        let data = legacy_geometry();
+        // Three f32 at offset 4 do not fit in a stride of 12 bytes
+        let vertices = vertices(
+            2,
+            &[[0x00, 4, 0x0C, 3, 12], [0x0E, 0, 0x0B, 9, 0x20]],
+            &data,
+        )
+        .unwrap();
+        let layouts = vertices.layouts();
+        assert_eq!(layouts[1].offset - layouts[0].offset, 0x1C);
+        assert_eq!(
+            (layouts[0].format, layouts[0].components, layouts[0].stride),
+            (ValueFormat::F32, 3, LEGACY_STRIDE)
+        );
+        assert_eq!(
+            (layouts[1].format, layouts[1].components, layouts[1].stride),
+            (ValueFormat::U8, 4, LEGACY_STRIDE)
+        );
+        let vertices = geometry(&vertices);
+        assert_eq!(vertices[0].position, [1.0, 2.0, 3.0]);
+        assert_eq!(vertices[0].color, Some([1.0, 0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn extra_texture_coordinates() {
+        let mut data = f32_bytes(&[1.0, 2.0, 3.0]);
+        data.extend(f32_bytes(&[0.123_456_7, 0.987_654_3]));
+        let vertices =
+            vertices(1, &[[0x00, 0, 0x0C, 3, 20], [0x15, 12, 0x0C, 2, 20]], &data).unwrap();
+        assert_eq!(
+            geometry(&vertices)[0].extra_texture_coordinates,
+            vec![[0.123_456_7, 0.987_654_3]]
+        );
+    }
+
+    #[test]
+    fn legacy_rigging() {
+        let mut data = vec![3, 4, 0, 0];
+        data.extend(f32_bytes(&[0.75, 0.25, 0.0, 0.0]));
+        data.extend_from_slice(&[0; 12]);
+        let vertices = vertices(1, &[[0x07, 0, 0, 0, 0], [0x01, 0, 0, 0, 0]], &data).unwrap();
+        match vertices.buffer {
+            VerticesDataBuffer::Rigging(ref r) => {
+                assert_eq!(r.vertices[0].nb_influences, 4);
+                assert_eq!(
+                    r.vertices[0].influences().collect::<Vec<_>>(),
+                    vec![(3, 0.75), (4, 0.25)]
+                );
+            }
+            _ => panic!("Expected rigging"),
+        }
+    }
+
+    #[test]
+    fn rigging_eight_influences() {
+        // 8 u16 joint indices, then 8 u16 weights
+        let mut data = Vec::new();
+        for joint in 300..308u16 {
+            data.extend_from_slice(&joint.to_le_bytes());
+        }
+        for weight in &[
+            0x8000u16, 0x4000, 0x2000, 0x1000, 0x0800, 0x0400, 0x0200, 0x01FF,
+        ] {
+            data.extend_from_slice(&weight.to_le_bytes());
+        }
+        let vertices = vertices(
+            1,
+            &[[0x07, 0, 0x02, 8, 0x20], [0x01, 0x10, 0x02, 8, 0x20]],
+            &data,
+        )
+        .unwrap();
+        let rigging = match vertices.buffer {
+            VerticesDataBuffer::Rigging(ref r) => &r.vertices[0],
+            _ => panic!("Expected rigging"),
+        };
+        assert_eq!(rigging.nb_influences, 8);
+        assert_eq!(rigging.joints, [300, 301, 302, 303, 304, 305, 306, 307]);
+        assert!((rigging.weights[0] - 32_768.0 / 65_535.0).abs() < 1e-6);
+        let sum = rigging.influences().map(|(_, w)| w).sum::<f32>();
+        assert!((sum - 1.0).abs() < 1e-6, "{}", sum);
+    }
+
+    fn faces(header: [u32; 2], nb_faces: u32, indices: &[u16]) -> Faces {
+        let mut bytes = Vec::new();
+        for word in &[0x45, 0x14, header[0], header[1], 0] {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        for index in indices {
+            bytes.extend_from_slice(&index.to_le_bytes());
+        }
+        Faces::import(&mut Cursor::new(bytes), nb_faces).unwrap()
+    }
+
+    fn points(faces: &Faces) -> Vec<(u32, u32, u32)> {
+        faces.triangles().map(|f| f.points).collect()
+    }
+
+    #[test]
+    fn triangle_strip() {
+        // 4 triangles take 6 indices, the rest is not read
+        let strip = faces([0x02, 0x05], 4, &[0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(strip.topology, Topology::TriangleStrip);
+        assert_eq!(strip.indices, vec![0, 1, 2, 3, 4, 5]);
+        // Every other triangle is flipped to keep the winding of the first one
+        assert_eq!(
+            points(&strip),
+            vec![(0, 1, 2), (2, 1, 3), (2, 3, 4), (4, 3, 5)]
+        );
+        assert!(faces([0x02, 0x05], 0, &[0, 1]).indices.is_empty());
+    }
+
+    #[test]
+    fn triangle_strip_joins() {
+        // Two strips joined by repeating the last index of the first one and the first index of the second one
+        let strip = faces([0x02, 0x05], 6, &[0, 1, 2, 2, 3, 3, 4, 5]);
+        assert_eq!(points(&strip), vec![(0, 1, 2), (4, 3, 5)]);
+    }
+
+    #[test]
+    fn unknown_face_format() {
+        // Read as a list of 16-bit indices
+        let list = faces([0x09, 0x09], 2, &[0, 1, 2, 2, 1, 3]);
+        assert_eq!(list.index_format, IndexFormat::U16);
+        assert_eq!(list.topology, Topology::TriangleList);
+        assert_eq!(points(&list), vec![(0, 1, 2), (2, 1, 3)]);
+    }
+
+    #[test]
+    fn mixed_attributes() {
+        match vertices(
+            1,
+            &[[0x00, 0, 0x0C, 3, 0x20], [0x01, 0x0C, 0x0C, 4, 0x20]],
+            &[0; 0x20],
+        ) {
+            Err(ISM2ImportError::InvalidVertexAttribute(0x01)) => {}
+            _ => panic!("Expected an invalid attribute"),
+        }
+        match vertices(1, &[], &[]) {
+            Err(ISM2ImportError::NoAttributes) => {}
+            _ => panic!("Expected no attributes"),
+        }
     }
 }
@@ -0,0 +1,147 @@
+use crate::model_data::{
+    Mesh, MeshSubSection, SubSection, Unnamed0A, Vertex, VertexGeometry, VertexRigging, Vertices,
+    VerticesDataBuffer,
+};
+use std::ops::Range;
+
+/// A mesh grouped with the vertex buffers its faces index into and its material.
+/// Faces are triangle lists rebased on `index_range` so that they index into `geometry_vertices` and `rigging_vertices` directly.
+/// Faces using a vertex past the end of the geometry buffer, or of the rigging buffer when there is one, are dropped.
+/// `ISM2::validate` reports the ones past the end of the geometry buffer as out-of-range indices.
+pub struct Primitive<'a> {
+    pub mesh: &'a Mesh,
+    pub geometry: Option<&'a Vertices>,
+    pub rigging: Option<&'a Vertices>,
+    /// Taken from the first header word of the mesh, which looks like a material index.
+    /// It is not resolved against the materials of the file, files seen so far only had a single texture.
+    pub material: u32,
+    /// Indices of the vertices used by the faces, in the vertex buffers
+    pub index_range: Range<u32>,
+}
+
+impl Unnamed0A {
+    /// Groups every mesh with the last geometry and rigging vertex buffers found before it.
+    /// A mesh found before any vertex buffer uses the first ones of the section.
+    pub fn primitives(&self) -> Vec<Primitive<'_>> {
+        let first_of = |geometry: bool| {
+            self.sub_sections.iter().find_map(|s| match s {
+                SubSection::Vertices(v) if is_geometry(v) == Some(geometry) => Some(v),
+                _ => None,
+            })
+        };
+        let mut geometry = first_of(true);
+        let mut rigging = first_of(false);
+        let mut primitives = Vec::new();
+        for sub_section in &self.sub_sections {
+            match sub_section {
+                SubSection::Vertices(v) => match is_geometry(v) {
+                    Some(true) => geometry = Some(v),
+                    Some(false) => rigging = Some(v),
+                    None => {}
+                },
+                SubSection::Mesh(mesh) => {
+                    primitives.push(Primitive::new(mesh, geometry, rigging));
+                }
+                _ => {}
+            }
+        }
+        primitives
+    }
+}
+
+impl<'a> Primitive<'a> {
+    pub fn new(
+        mesh: &'a Mesh,
+        geometry: Option<&'a Vertices>,
+        rigging: Option<&'a Vertices>,
+    ) -> Primitive<'a> {
+        let nb_vertices = buffer_len(geometry, rigging);
+        let mut indices = mesh_faces(mesh, nb_vertices).flat_map(|(a, b, c)| vec![a, b, c]);
+        let index_range = match indices.next() {
+            Some(first) => {
+                let (min, max) =
+                    indices.fold((first, first), |(min, max), i| (min.min(i), max.max(i)));
+                min..max + 1
+            }
+            None => 0..0,
+        };
+        Primitive {
+            mesh,
+            geometry,
+            rigging,
+            material: mesh.unknown[0],
+            index_range,
+        }
+    }
+
+    /// Faces of the mesh, rebased on the start of `index_range`
+    pub fn faces(&self) -> Vec<(u32, u32, u32)> {
+        let start = self.index_range.start;
+        mesh_faces(self.mesh, buffer_len(self.geometry, self.rigging))
+            .map(|(a, b, c)| (a - start, b - start, c - start))
+            .collect()
+    }
+
+    /// Geometry of the vertices in `index_range`
+    pub fn geometry_vertices(&self) -> &'a [VertexGeometry] {
+        match self.geometry.map(|v| &v.buffer) {
+            Some(VerticesDataBuffer::Geometry(g)) => slice(&g.vertices, &self.index_range),
+            _ => &[],
+        }
+    }
+
+    /// Decoded vertices in `index_range`, see `VertexGeometry::decode`
+    pub fn vertices(&self) -> Vec<Vertex> {
+        self.geometry_vertices()
+            .iter()
+            .map(VertexGeometry::decode)
+            .collect()
+    }
+
+    /// Rigging of the vertices in `index_range`, empty if there is no rigging buffer
+    pub fn rigging_vertices(&self) -> &'a [VertexRigging] {
+        match self.rigging.map(|v| &v.buffer) {
+            Some(VerticesDataBuffer::Rigging(r)) => slice(&r.vertices, &self.index_range),
+            _ => &[],
+        }
+    }
+}
+
+fn is_geometry(vertices: &Vertices) -> Option<bool> {
+    match vertices.buffer {
+        VerticesDataBuffer::Geometry(_) => Some(true),
+        VerticesDataBuffer::Rigging(_) => Some(false),
+        VerticesDataBuffer::Unknown08(_) => None,
+    }
+}
+
+/// Number of vertices the faces can use, that both the geometry and the rigging buffers have
+fn buffer_len(geometry: Option<&Vertices>, rigging: Option<&Vertices>) -> u32 {
+    let geometry_len = match geometry.map(|v| &v.buffer) {
+        Some(VerticesDataBuffer::Geometry(g)) => g.vertices.len(),
+        _ => 0,
+    };
+    let rigging_len = match rigging.map(|v| &v.buffer) {
+        Some(VerticesDataBuffer::Rigging(r)) => r.vertices.len(),
+        _ => geometry_len,
+    };
+    geometry_len.min(rigging_len) as u32
+}
+
+/// Triangles of the mesh whose vertices are all below `nb_vertices`
+fn mesh_faces<'a>(mesh: &'a Mesh, nb_vertices: u32) -> impl Iterator<Item = (u32, u32, u32)> + 'a {
+    mesh.sub_sections
+        .iter()
+        .filter_map(|s| match s {
+            MeshSubSection::Faces(f) => Some(f),
+            _ => None,
+        })
+        .flat_map(|f| f.triangles().map(|face| face.points))
+        .filter(move |&(a, b, c)| a < nb_vertices && b < nb_vertices && c < nb_vertices)
+}
+
+fn slice<'a, T>(slice: &'a [T], range: &Range<u32>) -> &'a [T] {
+    slice
+        .get(range.start as usize..range.end as usize)
+        .unwrap_or(&[])
+}
@@ -0,0 +1,468 @@
+use crate::joint_definition::{Joint, JointAttribute, JointDefinition, JointDefinitionSubSection};
+use crate::joint_extra::JointExtra;
+use crate::math::{self, Matrix4, Quaternion, RotationOrder};
+
+/// Joint hierarchy of a model, built from its Joint Definition section.
+/// Joints are indexed in the order they appear in the file, which is also the order used by `Joint::parent_index`.
+pub struct Skeleton<'a> {
+    joints: Vec<&'a Joint>,
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    roots: Vec<usize>,
+}
+
+/// Transformation attributes of a joint as stored in the file, angles in degrees.
+/// Missing attributes take their neutral value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JointTransform {
+    pub translate: [f32; 3],
+    pub rotate: [f32; 3],
+    pub joint_orient: [f32; 3],
+    pub scale: [f32; 3],
+}
+
+/// Transformation of a joint in bind pose
+#[derive(Clone, Copy, Debug)]
+pub struct JointPose {
+    pub translation: [f32; 3],
+    /// Joint orient and rotate combined
+    pub rotation: Quaternion,
+    pub scale: [f32; 3],
+    /// Relative to the parent joint
+    pub local_matrix: Matrix4,
+    /// Relative to the model
+    pub world_matrix: Matrix4,
+}
+
+/// Joint whose world matrix differs from its inverted inverse bind matrix
+#[derive(Clone, Debug)]
+pub struct BindPoseMismatch {
+    pub joint: usize,
+    pub name: String,
+    /// Largest difference between two elements of the matrices
+    pub max_difference: f32,
+}
+
+/// Result of comparing the joint hierarchy with the inverse bind matrices
+#[derive(Clone, Debug, Default)]
+pub struct BindPoseReport {
+    pub mismatches: Vec<BindPoseMismatch>,
+    /// Joints that have no inverse bind matrix
+    pub missing: Vec<usize>,
+    /// Joints whose inverse bind matrix cannot be inverted
+    pub singular: Vec<usize>,
+}
+
+/// Iterates over the ancestors of a joint, starting with its parent and ending with a root
+pub struct Ancestors<'s, 'a> {
+    skeleton: &'s Skeleton<'a>,
+    current: Option<usize>,
+    remaining: usize,
+}
+
+impl<'a> Skeleton<'a> {
+    pub fn new(joint_definition: &'a JointDefinition) -> Skeleton<'a> {
+        let joints: Vec<&Joint> = joint_definition
+            .sub_sections
+            .iter()
+            .filter_map(|s| match s {
+                JointDefinitionSubSection::Joint(j) => Some(j),
+                _ => None,
+            })
+            .collect();
+        let mut parents = Vec::with_capacity(joints.len());
+        let mut children = vec![Vec::new(); joints.len()];
+        let mut roots = Vec::new();
+        for (id, joint) in joints.iter().enumerate() {
+            match joint.parent_index {
+                Some(p) if p < joints.len() && p != id => {
+                    parents.push(Some(p));
+                    children[p].push(id);
+                }
+                _ => {
+                    parents.push(None);
+                    roots.push(id);
+                }
+            }
+        }
+        Skeleton {
+            joints,
+            parents,
+            children,
+            roots,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.joints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.joints.is_empty()
+    }
+
+    pub fn joints(&self) -> &[&'a Joint] {
+        &self.joints
+    }
+
+    pub fn joint(&self, index: usize) -> Option<&'a Joint> {
+        self.joints.get(index).cloned()
+    }
+
+    pub fn parent(&self, index: usize) -> Option<usize> {
+        self.parents.get(index).cloned().unwrap_or(None)
+    }
+
+    pub fn children(&self, index: usize) -> &[usize] {
+        match self.children.get(index) {
+            Some(c) => c,
+            None => &[],
+        }
+    }
+
+    /// Joints without a parent
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    /// Returns all joints reachable from the roots, every parent coming before its children
+    pub fn depth_first(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.joints.len());
+        let mut stack: Vec<usize> = self.roots.iter().rev().cloned().collect();
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            stack.extend(self.children[id].iter().rev());
+        }
+        order
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|j| j.name == name)
+    }
+
+    /// Finds the joint that vertex rigging data refers to with this ID
+    pub fn find_by_in_vertex_id(&self, in_vertex_id: u32) -> Option<usize> {
+        self.joints
+            .iter()
+            .position(|j| j.in_vertex_id == in_vertex_id)
+    }
+
+    pub fn ancestors(&self, index: usize) -> Ancestors<'_, 'a> {
+        Ancestors {
+            skeleton: self,
+            current: self.parent(index),
+            remaining: self.joints.len(),
+        }
+    }
+
+    pub fn transform(&self, index: usize) -> Option<JointTransform> {
+        self.joint(index).map(JointTransform::from_joint)
+    }
+
+    /// Order of the Rotate attributes of a joint, see `Joint::rotation_order`
+    pub fn rotation_order(&self, index: usize) -> Option<RotationOrder> {
+        self.joint(index).map(|j| j.rotation_order)
+    }
+
+    /// Transformation of a joint relative to its parent
+    pub fn local_matrix(&self, index: usize) -> Option<Matrix4> {
+        self.joint(index).map(local_matrix_of)
+    }
+
+    /// Transformation of a joint relative to the model
+    pub fn world_matrix(&self, index: usize) -> Option<Matrix4> {
+        let local = self.local_matrix(index)?;
+        Some(self.ancestors(index).fold(local, |m, parent| {
+            math::multiply(&local_matrix_of(self.joints[parent]), &m)
+        }))
+    }
+
+    /// Bind pose of every joint, in skeleton order
+    pub fn bind_pose(&self) -> Vec<JointPose> {
+        let mut world_matrices: Vec<Option<Matrix4>> = vec![None; self.joints.len()];
+        for id in self.depth_first() {
+            let local = local_matrix_of(self.joints[id]);
+            world_matrices[id] = Some(match self.parent(id).and_then(|p| world_matrices[p]) {
+                Some(parent) => math::multiply(&parent, &local),
+                None => local,
+            });
+        }
+        self.joints
+            .iter()
+            .enumerate()
+            .map(|(id, joint)| {
+                let transform = JointTransform::from_joint(joint);
+                JointPose {
+                    translation: transform.translate,
+                    rotation: Quaternion::from_matrix(
+                        &transform.rotation_matrix(joint.rotation_order),
+                    ),
+                    scale: transform.scale,
+                    local_matrix: transform.local_matrix(joint.rotation_order),
+                    world_matrix: world_matrices[id]
+                        .or_else(|| self.world_matrix(id))
+                        .unwrap_or(math::IDENTITY),
+                }
+            })
+            .collect()
+    }
+}
+
+impl<'a> Skeleton<'a> {
+    /// Looks up the inverse bind matrix of every joint by name. The first match wins when a bone appears in several skin bindings.
+    pub fn inverse_bind_matrices_from(&self, joint_extra: &JointExtra) -> Vec<Option<Matrix4>> {
+        let bindings = joint_extra.skin_bindings();
+        self.joints
+            .iter()
+            .map(|j| {
+                bindings
+                    .iter()
+                    .find_map(|b| b.inverse_bind_matrix(&j.name))
+                    .cloned()
+            })
+            .collect()
+    }
+
+    /// Compares the world matrix of every joint with its inverted inverse bind matrix.
+    /// `inverse_bind_matrices` is indexed like the joints of this skeleton.
+    pub fn check_inverse_bind_matrices(
+        &self,
+        inverse_bind_matrices: &[Option<Matrix4>],
+        tolerance: f32,
+    ) -> BindPoseReport {
+        let mut report = BindPoseReport::default();
+        for (id, pose) in self.bind_pose().iter().enumerate() {
+            let ibm = match inverse_bind_matrices.get(id).cloned().unwrap_or(None) {
+                Some(m) => m,
+                None => {
+                    report.missing.push(id);
+                    continue;
+                }
+            };
+            let world = match math::invert(&ibm) {
+                Some(w) => w,
+                None => {
+                    report.singular.push(id);
+                    continue;
+                }
+            };
+            let max_difference = world
+                .iter()
+                .zip(pose.world_matrix.iter())
+                .map(|(a, b)| (a - b).abs())
+                .fold(0f32, f32::max);
+            if max_difference > tolerance {
+                report.mismatches.push(BindPoseMismatch {
+                    joint: id,
+                    name: self.joints[id].name.clone(),
+                    max_difference,
+                });
+            }
+        }
+        report
+    }
+
+    /// Builds inverse bind matrices from the joint hierarchy, for files that lack them
+    pub fn inverse_bind_matrices(&self) -> Vec<Matrix4> {
+        self.bind_pose()
+            .iter()
+            .map(|p| math::invert(&p.world_matrix).unwrap_or(math::IDENTITY))
+            .collect()
+    }
+
+    /// Rebuilds the bind pose from inverse bind matrices, for joints that lack transformation attributes.
+    /// Joints without a usable matrix get `None`, their children are then computed relative to the hierarchy.
+    pub fn bind_pose_from_inverse_bind_matrices(
+        &self,
+        inverse_bind_matrices: &[Option<Matrix4>],
+    ) -> Vec<Option<JointPose>> {
+        let computed = self.bind_pose();
+        let world_matrices: Vec<Option<Matrix4>> = (0..self.joints.len())
+            .map(|id| {
+                inverse_bind_matrices
+                    .get(id)
+                    .cloned()
+                    .unwrap_or(None)
+                    .and_then(|m| math::invert(&m))
+            })
+            .collect();
+        (0..self.joints.len())
+            .map(|id| {
+                let world_matrix = world_matrices[id]?;
+                let local_matrix = match self.parent(id) {
+                    Some(p) => {
+                        let parent_world = world_matrices[p].unwrap_or(computed[p].world_matrix);
+                        math::multiply(&math::invert(&parent_world)?, &world_matrix)
+                    }
+                    None => world_matrix,
+                };
+                let (translation, rotation, scale) = math::decompose(&local_matrix);
+                Some(JointPose {
+                    translation,
+                    rotation,
+                    scale,
+                    local_matrix,
+                    world_matrix,
+                })
+            })
+            .collect()
+    }
+}
+
+impl JointTransform {
+    pub fn from_joint(joint: &Joint) -> JointTransform {
+        let mut transform = JointTransform {
+            translate: [0.0; 3],
+            rotate: [0.0; 3],
+            joint_orient: [0.0; 3],
+            scale: [1.0; 3],
+        };
+        for attribute in joint.attributes() {
+            match attribute {
+                JointAttribute::Translate(t) => transform.translate = [t.x, t.y, t.z],
+                JointAttribute::Scale(s) => transform.scale = [s.x, s.y, s.z],
+                JointAttribute::RotateX(r) => transform.rotate[0] = r.angle,
+                JointAttribute::RotateY(r) => transform.rotate[1] = r.angle,
+                JointAttribute::RotateZ(r) => transform.rotate[2] = r.angle,
+                JointAttribute::JointOrientX(o) => transform.joint_orient[0] = o.angle,
+                JointAttribute::JointOrientY(o) => transform.joint_orient[1] = o.angle,
+                JointAttribute::JointOrientZ(o) => transform.joint_orient[2] = o.angle,
+                _ => {}
+            }
+        }
+        transform
+    }
+
+    /// Rotation relative to the parent joint, Joint Orient applied after Rotate like in Maya
+    pub fn rotation_matrix(&self, rotation_order: RotationOrder) -> Matrix4 {
+        let orient = math::euler_rotation(to_radians(self.joint_orient), RotationOrder::Xyz);
+        let rotate = math::euler_rotation(to_radians(self.rotate), rotation_order);
+        math::multiply(&orient, &rotate)
+    }
+
+    /// Translate * Joint Orient * Rotate * Scale
+    pub fn local_matrix(&self, rotation_order: RotationOrder) -> Matrix4 {
+        math::multiply(
+            &math::multiply(
+                &math::translation(self.translate),
+                &self.rotation_matrix(rotation_order),
+            ),
+            &math::scale(self.scale),
+        )
+    }
+}
+
+fn local_matrix_of(joint: &Joint) -> Matrix4 {
+    JointTransform::from_joint(joint).local_matrix(joint.rotation_order)
+}
+
+fn to_radians(degrees: [f32; 3]) -> [f32; 3] {
+    [
+        degrees[0].to_radians(),
+        degrees[1].to_radians(),
+        degrees[2].to_radians(),
+    ]
+}
+
+impl<'s, 'a> Iterator for Ancestors<'s, 'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        // Bounded by the number of joints so that a malformed hierarchy cannot loop forever
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let id = self.current?;
+        self.current = self.skeleton.parent(id);
+        Some(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::joint_definition::{
+        JointAttributesOffsets, JointRotateZ, JointSubSection, JointTranslate,
+    };
+
+    fn joint(name: &str, parent_index: Option<usize>, translate: f32, rotate_z: f32) -> Joint {
+        Joint {
+            name: name.to_string(),
+            parent_index,
+            sub_sections: vec![JointSubSection::Offsets(JointAttributesOffsets {
+                attributes: vec![
+                    JointAttribute::Translate(JointTranslate {
+                        header: 0,
+                        x: translate,
+                        y: 0.0,
+                        z: 0.0,
+                    }),
+                    JointAttribute::RotateZ(JointRotateZ {
+                        header: [0; 4],
+                        angle: rotate_z,
+                    }),
+                ],
+            })],
+            in_vertex_id: 0,
+            rotation_order: RotationOrder::Xyz,
+        }
+    }
+
+    fn assert_close(a: &Matrix4, b: &Matrix4) {
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn bind_pose() {
+        // The child comes first, the root is turned a quarter around Z
+        let joint_definition = JointDefinition {
+            sub_sections: vec![
+                JointDefinitionSubSection::Joint(joint("child", Some(1), 1.0, 0.0)),
+                JointDefinitionSubSection::Joint(joint("root", None, 1.0, 90.0)),
+            ],
+        };
+        let skeleton = Skeleton::new(&joint_definition);
+        assert_eq!(skeleton.depth_first(), vec![1, 0]);
+        let pose = skeleton.bind_pose();
+        let child = skeleton.world_matrix(0).unwrap();
+        assert_close(&pose[0].world_matrix, &child);
+        assert_close(&pose[1].world_matrix, &pose[1].local_matrix);
+        assert!((child[3] - 1.0).abs() < 1e-6);
+        assert!((child[7] - 1.0).abs() < 1e-6);
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((pose[1].rotation.z - half).abs() < 1e-6);
+        assert!((pose[1].rotation.w - half).abs() < 1e-6);
+        assert_eq!(pose[1].translation, [1.0, 0.0, 0.0]);
+        assert_eq!(pose[1].scale, [1.0; 3]);
+        for (p, ibm) in pose.iter().zip(skeleton.inverse_bind_matrices().iter()) {
+            assert_close(&math::multiply(&p.world_matrix, ibm), &math::IDENTITY);
+        }
+        let report = skeleton.check_inverse_bind_matrices(
+            &skeleton
+                .inverse_bind_matrices()
+                .into_iter()
+                .map(Some)
+                .collect::<Vec<_>>(),
+            1e-5,
+        );
+        assert!(report.mismatches.is_empty() && report.missing.is_empty());
+    }
+
+    #[test]
+    fn out_of_range() {
+        let joint_definition = JointDefinition {
+            sub_sections: vec![JointDefinitionSubSection::Joint(joint(
+                "root", None, 0.0, 0.0,
+            ))],
+        };
+        let skeleton = Skeleton::new(&joint_definition);
+        assert!(skeleton.transform(1).is_none());
+        assert!(skeleton.rotation_order(1).is_none());
+        assert!(skeleton.local_matrix(1).is_none());
+        assert!(skeleton.world_matrix(1).is_none());
+        assert_close(&skeleton.world_matrix(0).unwrap(), &math::IDENTITY);
+    }
+}
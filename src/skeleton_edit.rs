@@ -0,0 +1,735 @@
+use crate::error::SkeletonEditError;
+use crate::joint_definition::{
+    rotation_order_of, Joint, JointAttribute, JointAttributesOffsets, JointDefinitionSubSection,
+    JointOrientX, JointOrientY, JointOrientZ, JointRotateX, JointRotateY, JointRotateZ, JointScale,
+    JointSubSection, JointTranslate,
+};
+use crate::joint_extra::{BufferData, Unnamed30};
+use crate::math::{self, RotationOrder};
+use crate::model_data::{SubSection, VertexRigging, VerticesDataBuffer};
+use crate::skeleton::{JointTransform, Skeleton};
+use crate::{Section, ISM2};
+
+type Result<T> = std::result::Result<T, SkeletonEditError>;
+
+/// Edits that keep the joint definition, the bone lists of Joint Extra and the vertex rigging consistent with each other.
+/// Joints are referred to by their index in the skeleton, see `Skeleton`.
+impl ISM2 {
+    /// Renames a joint everywhere it is referred to by name
+    pub fn rename_joint(&mut self, index: usize, name: &str) -> Result<()> {
+        let old_name = {
+            let joints = self.joints_mut()?;
+            if joints
+                .iter()
+                .enumerate()
+                .any(|(i, j)| i != index && j.name == name)
+            {
+                return Err(SkeletonEditError::DuplicateName(name.to_string()));
+            }
+            let joint = joints
+                .into_iter()
+                .nth(index)
+                .ok_or(SkeletonEditError::NoSuchJoint(index))?;
+            std::mem::replace(&mut joint.name, name.to_string())
+        };
+        for s30 in self.skin_sub_sections_mut() {
+            for buffer in &mut s30.sub_sections {
+                if let BufferData::Strings(ref mut names) = buffer.data {
+                    for n in names.iter_mut().filter(|n| **n == old_name) {
+                        *n = name.to_string();
+                    }
+                }
+            }
+        }
+        self.intern_string(name);
+        Ok(())
+    }
+
+    /// Moves a joint under another one, or makes it a root.
+    /// Translate, Rotate and Scale of the joint are recomputed so that it keeps its place in the model, see `transform_under`.
+    pub fn reparent_joint(&mut self, index: usize, parent: Option<usize>) -> Result<()> {
+        let transform = {
+            let skeleton = self.skeleton().ok_or(SkeletonEditError::NoSkeleton)?;
+            if index >= skeleton.len() {
+                return Err(SkeletonEditError::NoSuchJoint(index));
+            }
+            if let Some(p) = parent {
+                if p >= skeleton.len() {
+                    return Err(SkeletonEditError::NoSuchJoint(p));
+                }
+                // The new parent cannot be the joint itself or one of its descendants
+                if p == index || skeleton.ancestors(p).any(|a| a == index) {
+                    return Err(SkeletonEditError::WouldCreateCycle);
+                }
+            }
+            transform_under(&skeleton, index, parent)?
+        };
+        let mut joints = self.joints_mut()?;
+        joints[index].parent_index = parent;
+        set_transform(joints[index], &transform);
+        Ok(())
+    }
+
+    /// Appends a new joint to the skeleton and returns its index. Its attributes are written in XYZ rotation order.
+    /// It gets the next free in-vertex ID. When `binding` is given, the joint is added to the bone list of that skin binding,
+    /// indexed like `JointExtra::skin_bindings`, with an inverse bind matrix matching its bind pose.
+    pub fn add_joint(
+        &mut self,
+        name: &str,
+        parent: Option<usize>,
+        transform: &JointTransform,
+        binding: Option<usize>,
+    ) -> Result<usize> {
+        if let Some(b) = binding {
+            if b >= self.skin_sub_sections_mut().len() {
+                return Err(SkeletonEditError::NoSuchBinding(b));
+            }
+        }
+        let attributes = transform_attributes(transform);
+        let rotation_order = rotation_order_of(attributes.iter());
+        let (index, in_vertex_id, inverse_bind_matrix) = {
+            let skeleton = self.skeleton().ok_or(SkeletonEditError::NoSkeleton)?;
+            if skeleton.find_by_name(name).is_some() {
+                return Err(SkeletonEditError::DuplicateName(name.to_string()));
+            }
+            let parent_world = match parent {
+                Some(p) => skeleton
+                    .world_matrix(p)
+                    .ok_or(SkeletonEditError::NoSuchJoint(p))?,
+                None => math::IDENTITY,
+            };
+            let world = math::multiply(&parent_world, &transform.local_matrix(rotation_order));
+            let in_vertex_id = skeleton
+                .joints()
+                .iter()
+                .map(|j| j.in_vertex_id + 1)
+                .max()
+                .unwrap_or(0);
+            (
+                skeleton.len(),
+                in_vertex_id,
+                math::invert(&world).unwrap_or(math::IDENTITY),
+            )
+        };
+        let joint = Joint {
+            name: name.to_string(),
+            parent_index: parent,
+            sub_sections: vec![JointSubSection::Offsets(JointAttributesOffsets {
+                attributes,
+            })],
+            in_vertex_id,
+            rotation_order,
+        };
+        for section in &mut self.sections {
+            if let Section::JointDefinition(jd) = section {
+                jd.sub_sections
+                    .push(JointDefinitionSubSection::Joint(joint));
+                break;
+            }
+        }
+        if let Some(s30) = binding.and_then(|b| self.skin_sub_sections_mut().into_iter().nth(b)) {
+            for buffer in &mut s30.sub_sections {
+                match buffer.data {
+                    BufferData::Strings(ref mut names) => names.push(name.to_string()),
+                    BufferData::F32(ref mut values) if buffer.components == 16 => {
+                        values.extend_from_slice(&inverse_bind_matrix)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.intern_string(name);
+        Ok(index)
+    }
+
+    /// Removes a joint. Its children are attached to its parent, keeping their place in the model, and the vertices bound to it
+    /// are bound to its parent instead, the weights being added up when a vertex was bound to both.
+    /// In-vertex IDs above the one of the removed joint are shifted down, in the joints as well as in the rigging data.
+    pub fn remove_joint(&mut self, index: usize) -> Result<()> {
+        let (name, parent, removed_id, parent_id, children) = {
+            let skeleton = self.skeleton().ok_or(SkeletonEditError::NoSkeleton)?;
+            let joint = skeleton
+                .joint(index)
+                .ok_or(SkeletonEditError::NoSuchJoint(index))?;
+            let parent = skeleton.parent(index);
+            let children = skeleton
+                .children(index)
+                .iter()
+                .map(|&c| Ok((c, transform_under(&skeleton, c, parent)?)))
+                .collect::<Result<Vec<(usize, JointTransform)>>>()?;
+            (
+                joint.name.clone(),
+                parent,
+                joint.in_vertex_id,
+                parent.map(|p| skeleton.joints()[p].in_vertex_id),
+                children,
+            )
+        };
+        let mut riggings = self.riggings_mut();
+        if parent_id.is_none() && riggings.iter().any(|r| uses_joint(r, removed_id)) {
+            return Err(SkeletonEditError::UnboundInfluences(name));
+        }
+        let remap = |id: u32| -> u32 {
+            let id = if id == removed_id {
+                parent_id.unwrap_or(id)
+            } else {
+                id
+            };
+            if id > removed_id {
+                id - 1
+            } else {
+                id
+            }
+        };
+        for rigging in riggings.iter_mut() {
+            let nb_influences = rigging.nb_influences;
+            for j in rigging.joints.iter_mut().take(nb_influences) {
+                *j = remap(u32::from(*j)) as u16;
+            }
+            merge_influences(rigging);
+        }
+        for section in &mut self.sections {
+            if let Section::JointDefinition(jd) = section {
+                let mut joint_counter = 0usize;
+                jd.sub_sections.retain(|s| match s {
+                    JointDefinitionSubSection::Joint(_) => {
+                        joint_counter += 1;
+                        joint_counter - 1 != index
+                    }
+                    _ => true,
+                });
+                break;
+            }
+        }
+        for joint in self.joints_mut()? {
+            joint.parent_index = match joint.parent_index {
+                Some(p) if p == index => parent,
+                p => p,
+            }
+            .map(|p| if p > index { p - 1 } else { p });
+            if joint.in_vertex_id > removed_id {
+                joint.in_vertex_id -= 1;
+            }
+        }
+        let mut joints = self.joints_mut()?;
+        for (child, transform) in children {
+            // Children may come before their parent in the file
+            let child = if child > index { child - 1 } else { child };
+            set_transform(joints[child], &transform);
+        }
+        for s30 in self.skin_sub_sections_mut() {
+            let position = s30
+                .sub_sections
+                .iter()
+                .find_map(|b| b.bone_names()?.iter().position(|n| *n == name));
+            if let Some(position) = position {
+                for buffer in &mut s30.sub_sections {
+                    match buffer.data {
+                        BufferData::Strings(ref mut names) => {
+                            names.remove(position);
+                        }
+                        BufferData::F32(ref mut values) if buffer.components == 16 => {
+                            let range = position * 16..(position + 1) * 16;
+                            if range.end <= values.len() {
+                                values.drain(range);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn joints_mut(&mut self) -> Result<Vec<&mut Joint>> {
+        for section in &mut self.sections {
+            if let Section::JointDefinition(jd) = section {
+                return Ok(jd
+                    .sub_sections
+                    .iter_mut()
+                    .filter_map(|s| match s {
+                        JointDefinitionSubSection::Joint(j) => Some(j),
+                        _ => None,
+                    })
+                    .collect());
+            }
+        }
+        Err(SkeletonEditError::NoSkeleton)
+    }
+
+    fn skin_sub_sections_mut(&mut self) -> Vec<&mut Unnamed30> {
+        let mut sub_sections = Vec::new();
+        for section in &mut self.sections {
+            if let Section::JointExtra(je) = section {
+                for s31 in &mut je.sub_sections {
+                    sub_sections.extend(s31.sub_sections.iter_mut());
+                }
+            }
+        }
+        sub_sections
+    }
+
+    fn riggings_mut(&mut self) -> Vec<&mut VertexRigging> {
+        let mut riggings = Vec::new();
+        for section in &mut self.sections {
+            if let Section::ModelData(md) = section {
+                for sub_section in md.groups.iter_mut().flat_map(|g| g.sub_sections.iter_mut()) {
+                    if let SubSection::Vertices(v) = sub_section {
+                        if let VerticesDataBuffer::Rigging(ref mut r) = v.buffer {
+                            riggings.extend(r.vertices.iter_mut());
+                        }
+                    }
+                }
+            }
+        }
+        riggings
+    }
+
+    /// Adds a string to the string table if it is not there yet
+    fn intern_string(&mut self, s: &str) {
+        if !self.string_table.iter().any(|e| e == s) {
+            self.string_table.push(s.to_string());
+        }
+    }
+}
+
+fn uses_joint(rigging: &VertexRigging, in_vertex_id: u32) -> bool {
+    rigging
+        .influences()
+        .any(|(j, _)| u32::from(j) == in_vertex_id)
+}
+
+/// Adds up the weights of the slots that refer to the same joint, freed slots are zeroed at the end
+fn merge_influences(rigging: &mut VertexRigging) {
+    let mut nb_merged = 0;
+    for slot in 0..rigging.nb_influences {
+        let (joint, weight) = (rigging.joints[slot], rigging.weights[slot]);
+        if !(weight.is_finite() && weight > 0.0) {
+            continue;
+        }
+        match (0..nb_merged).find(|&m| rigging.joints[m] == joint) {
+            Some(m) => rigging.weights[m] += weight,
+            None => {
+                rigging.joints[nb_merged] = joint;
+                rigging.weights[nb_merged] = weight;
+                nb_merged += 1;
+            }
+        }
+    }
+    for slot in nb_merged..rigging.nb_influences {
+        rigging.joints[slot] = 0;
+        rigging.weights[slot] = 0.0;
+    }
+}
+
+/// Local transformation that keeps a joint at the same place in the model once it is under `parent`.
+/// Joint Orient is kept and Translate, Rotate and Scale take up the difference, which is exact unless a non-uniform scale is involved.
+fn transform_under(
+    skeleton: &Skeleton,
+    index: usize,
+    parent: Option<usize>,
+) -> Result<JointTransform> {
+    let joint = skeleton
+        .joint(index)
+        .ok_or(SkeletonEditError::NoSuchJoint(index))?;
+    let mut transform = JointTransform::from_joint(joint);
+    let world = skeleton
+        .world_matrix(index)
+        .ok_or(SkeletonEditError::NoSuchJoint(index))?;
+    let local = match parent {
+        Some(p) => {
+            let parent_world = skeleton
+                .world_matrix(p)
+                .ok_or(SkeletonEditError::NoSuchJoint(p))?;
+            match math::invert(&parent_world) {
+                Some(inverse_parent) => math::multiply(&inverse_parent, &world),
+                None => return Ok(transform),
+            }
+        }
+        None => world,
+    };
+    let (translation, rotation, scale) = math::decompose(&local);
+    let orient = math::euler_rotation(
+        [
+            transform.joint_orient[0].to_radians(),
+            transform.joint_orient[1].to_radians(),
+            transform.joint_orient[2].to_radians(),
+        ],
+        RotationOrder::Xyz,
+    );
+    let rotate = math::multiply(&math::transpose(&orient), &rotation.to_matrix());
+    let angles = math::euler_angles(&rotate, joint.rotation_order);
+    transform.translate = translation;
+    transform.rotate = [
+        angles[0].to_degrees(),
+        angles[1].to_degrees(),
+        angles[2].to_degrees(),
+    ];
+    transform.scale = scale;
+    Ok(transform)
+}
+
+/// Writes a transformation to the attributes of a joint, adding the attributes it lacks at the end of its first offsets table.
+/// Missing Rotate attributes are added in X, Y, Z order, which leaves `Joint::rotation_order` unchanged.
+fn set_transform(joint: &mut Joint, transform: &JointTransform) {
+    let t = transform;
+    let mut present = [false; 8];
+    for sub_section in &mut joint.sub_sections {
+        if let JointSubSection::Offsets(o) = sub_section {
+            for attribute in &mut o.attributes {
+                let kind = match attribute {
+                    JointAttribute::Translate(a) => {
+                        a.x = t.translate[0];
+                        a.y = t.translate[1];
+                        a.z = t.translate[2];
+                        0
+                    }
+                    JointAttribute::Scale(a) => {
+                        a.x = t.scale[0];
+                        a.y = t.scale[1];
+                        a.z = t.scale[2];
+                        1
+                    }
+                    JointAttribute::RotateX(a) => {
+                        a.angle = t.rotate[0];
+                        2
+                    }
+                    JointAttribute::RotateY(a) => {
+                        a.angle = t.rotate[1];
+                        3
+                    }
+                    JointAttribute::RotateZ(a) => {
+                        a.angle = t.rotate[2];
+                        4
+                    }
+                    JointAttribute::JointOrientX(a) => {
+                        a.angle = t.joint_orient[0];
+                        5
+                    }
+                    JointAttribute::JointOrientY(a) => {
+                        a.angle = t.joint_orient[1];
+                        6
+                    }
+                    JointAttribute::JointOrientZ(a) => {
+                        a.angle = t.joint_orient[2];
+                        7
+                    }
+                    _ => continue,
+                };
+                present[kind] = true;
+            }
+        }
+    }
+    // Same order as `present`
+    let missing: Vec<JointAttribute> = transform_attributes(t)
+        .into_iter()
+        .zip(present.iter())
+        .filter(|(_, present)| !**present)
+        .map(|(a, _)| a)
+        .collect();
+    let offsets = joint.sub_sections.iter_mut().find_map(|s| match s {
+        JointSubSection::Offsets(o) => Some(o),
+        _ => None,
+    });
+    match offsets {
+        Some(o) => o.attributes.extend(missing),
+        None => joint
+            .sub_sections
+            .push(JointSubSection::Offsets(JointAttributesOffsets {
+                attributes: missing,
+            })),
+    }
+}
+
+fn transform_attributes(transform: &JointTransform) -> Vec<JointAttribute> {
+    let t = transform;
+    vec![
+        JointAttribute::Translate(JointTranslate {
+            header: 0,
+            x: t.translate[0],
+            y: t.translate[1],
+            z: t.translate[2],
+        }),
+        JointAttribute::Scale(JointScale {
+            header: 0,
+            x: t.scale[0],
+            y: t.scale[1],
+            z: t.scale[2],
+        }),
+        JointAttribute::RotateX(JointRotateX {
+            header: [0; 4],
+            angle: t.rotate[0],
+        }),
+        JointAttribute::RotateY(JointRotateY {
+            header: [0; 4],
+            angle: t.rotate[1],
+        }),
+        JointAttribute::RotateZ(JointRotateZ {
+            header: [0; 4],
+            angle: t.rotate[2],
+        }),
+        JointAttribute::JointOrientX(JointOrientX {
+            header: [0; 4],
+            angle: t.joint_orient[0],
+        }),
+        JointAttribute::JointOrientY(JointOrientY {
+            header: [0; 4],
+            angle: t.joint_orient[1],
+        }),
+        JointAttribute::JointOrientZ(JointOrientZ {
+            header: [0; 4],
+            angle: t.joint_orient[2],
+        }),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::joint_definition::JointDefinition;
+    use crate::joint_extra::{Buffer, ElementType, JointExtra, Unnamed31};
+    use crate::math::Matrix4;
+    use crate::model_data::{ModelData, Unnamed0A, Vertices, VerticesRiggingBuffer};
+
+    fn joint(
+        name: &str,
+        parent_index: Option<usize>,
+        in_vertex_id: u32,
+        translate: [f32; 3],
+    ) -> Joint {
+        let transform = JointTransform {
+            translate,
+            rotate: [10.0, 20.0, 30.0],
+            joint_orient: [0.0, 0.0, 45.0],
+            scale: [1.0; 3],
+        };
+        Joint {
+            name: name.to_string(),
+            parent_index,
+            sub_sections: vec![JointSubSection::Offsets(JointAttributesOffsets {
+                attributes: transform_attributes(&transform),
+            })],
+            in_vertex_id,
+            rotation_order: RotationOrder::Xyz,
+        }
+    }
+
+    fn rigging(influences: &[(u16, f32)]) -> VertexRigging {
+        let mut rigging = VertexRigging {
+            joints: [0; 8],
+            weights: [0.0; 8],
+            nb_influences: 4,
+        };
+        for (slot, &(joint, weight)) in influences.iter().enumerate() {
+            rigging.joints[slot] = joint;
+            rigging.weights[slot] = weight;
+        }
+        rigging
+    }
+
+    /// "child" comes before its parent "mid", which is under "root".
+    /// The inverse bind matrices are filled with the in-vertex ID of their joint to tell them apart.
+    fn model() -> ISM2 {
+        let joints = vec![
+            joint("child", Some(2), 2, [2.0, 0.0, 0.0]),
+            joint("root", None, 0, [1.0, 0.0, 0.0]),
+            joint("mid", Some(1), 1, [0.0, 1.0, 0.0]),
+        ];
+        let mut inverse_bind_matrices = vec![0.0; 16];
+        inverse_bind_matrices.extend_from_slice(&[1.0; 16]);
+        inverse_bind_matrices.extend_from_slice(&[2.0; 16]);
+        let skin = Unnamed30 {
+            unknown1: 0,
+            identity_matrix: math::IDENTITY,
+            sub_sections: vec![
+                Buffer {
+                    element_type: ElementType::StringReference,
+                    components: 1,
+                    stride: 0,
+                    data: BufferData::Strings(vec![
+                        "root".to_string(),
+                        "mid".to_string(),
+                        "child".to_string(),
+                    ]),
+                },
+                Buffer {
+                    element_type: ElementType::F32,
+                    components: 16,
+                    stride: 16,
+                    data: BufferData::F32(inverse_bind_matrices),
+                },
+            ],
+        };
+        let vertices = Vertices {
+            nb_vertices: 3,
+            attributes: Vec::new(),
+            buffer: VerticesDataBuffer::Rigging(VerticesRiggingBuffer {
+                vertices: vec![
+                    rigging(&[(1, 0.25), (0, 0.75)]),
+                    rigging(&[(2, 1.0)]),
+                    rigging(&[(0, 0.5), (2, 0.5)]),
+                ],
+            }),
+        };
+        ISM2 {
+            version: 1,
+            file_size: 0,
+            string_table: vec!["child".to_string(), "root".to_string(), "mid".to_string()],
+            sections: vec![
+                Section::JointDefinition(JointDefinition {
+                    sub_sections: joints
+                        .into_iter()
+                        .map(JointDefinitionSubSection::Joint)
+                        .collect(),
+                }),
+                Section::JointExtra(JointExtra {
+                    sub_sections: vec![Unnamed31 {
+                        name1: String::new(),
+                        name2: String::new(),
+                        sub_sections: vec![skin],
+                    }],
+                }),
+                Section::ModelData(ModelData {
+                    groups: vec![Unnamed0A {
+                        sub_sections: vec![SubSection::Vertices(vertices)],
+                    }],
+                }),
+            ],
+        }
+    }
+
+    fn world_matrices(ism2: &ISM2) -> Vec<Matrix4> {
+        let skeleton = ism2.skeleton().unwrap();
+        (0..skeleton.len())
+            .map(|i| skeleton.world_matrix(i).unwrap())
+            .collect()
+    }
+
+    fn assert_close(a: &Matrix4, b: &Matrix4) {
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-4, "{:?} != {:?}", a, b);
+        }
+    }
+
+    fn skin_buffers(ism2: &mut ISM2) -> (Vec<String>, Vec<f32>) {
+        let s30 = ism2.skin_sub_sections_mut().into_iter().next().unwrap();
+        let mut names = Vec::new();
+        let mut matrices = Vec::new();
+        for buffer in &s30.sub_sections {
+            match buffer.data {
+                BufferData::Strings(ref n) => names = n.clone(),
+                BufferData::F32(ref m) => matrices = m.clone(),
+                _ => {}
+            }
+        }
+        (names, matrices)
+    }
+
+    #[test]
+    fn rename() {
+        let mut ism2 = model();
+        match ism2.rename_joint(0, "root") {
+            Err(SkeletonEditError::DuplicateName(name)) => assert_eq!(name, "root"),
+            _ => panic!("Expected a duplicate name"),
+        }
+        ism2.rename_joint(2, "spine").unwrap();
+        assert_eq!(ism2.skeleton().unwrap().find_by_name("spine"), Some(2));
+        assert_eq!(skin_buffers(&mut ism2).0, vec!["root", "spine", "child"]);
+        assert!(ism2.string_table.iter().any(|s| s == "spine"));
+    }
+
+    #[test]
+    fn reparent() {
+        let mut ism2 = model();
+        let before = world_matrices(&ism2);
+        match ism2.reparent_joint(1, Some(0)) {
+            Err(SkeletonEditError::WouldCreateCycle) => {}
+            _ => panic!("Expected a cycle"),
+        }
+        ism2.reparent_joint(0, None).unwrap();
+        assert_eq!(ism2.skeleton().unwrap().parent(0), None);
+        assert_close(&world_matrices(&ism2)[0], &before[0]);
+        ism2.reparent_joint(0, Some(1)).unwrap();
+        assert_eq!(ism2.skeleton().unwrap().parent(0), Some(1));
+        for (after, before) in world_matrices(&ism2).iter().zip(before.iter()) {
+            assert_close(after, before);
+        }
+    }
+
+    #[test]
+    fn add() {
+        let mut ism2 = model();
+        let transform = JointTransform {
+            translate: [0.0, 0.0, 3.0],
+            rotate: [0.0, 90.0, 0.0],
+            joint_orient: [0.0; 3],
+            scale: [1.0; 3],
+        };
+        match ism2.add_joint("tip", Some(0), &transform, Some(1)) {
+            Err(SkeletonEditError::NoSuchBinding(1)) => {}
+            _ => panic!("Expected a missing binding"),
+        }
+        match ism2.add_joint("mid", Some(0), &transform, None) {
+            Err(SkeletonEditError::DuplicateName(_)) => {}
+            _ => panic!("Expected a duplicate name"),
+        }
+        assert_eq!(
+            ism2.add_joint("tip", Some(0), &transform, Some(0)).unwrap(),
+            3
+        );
+        let world = world_matrices(&ism2)[3];
+        let skeleton = ism2.skeleton().unwrap();
+        assert_eq!(skeleton.parent(3), Some(0));
+        assert_eq!(skeleton.joint(3).unwrap().in_vertex_id, 3);
+        let (names, matrices) = skin_buffers(&mut ism2);
+        assert_eq!(names, vec!["root", "mid", "child", "tip"]);
+        let mut inverse_bind_matrix = [0f32; 16];
+        inverse_bind_matrix.copy_from_slice(&matrices[48..64]);
+        assert_close(
+            &math::multiply(&world, &inverse_bind_matrix),
+            &math::IDENTITY,
+        );
+    }
+
+    #[test]
+    fn remove_parent_after_child() {
+        let mut ism2 = model();
+        let before = world_matrices(&ism2);
+        ism2.remove_joint(2).unwrap();
+        let skeleton = ism2.skeleton().unwrap();
+        assert_eq!(skeleton.len(), 2);
+        assert_eq!(skeleton.parent(0), Some(1));
+        assert_eq!(skeleton.joint(0).unwrap().in_vertex_id, 1);
+        assert_eq!(skeleton.joint(1).unwrap().in_vertex_id, 0);
+        let after = world_matrices(&ism2);
+        assert_close(&after[0], &before[0]);
+        assert_close(&after[1], &before[1]);
+        // Influences of "mid" go to "root" and are merged with the ones already there
+        let riggings = ism2.riggings_mut();
+        assert_eq!(riggings[0].influences().collect::<Vec<_>>(), vec![(0, 1.0)]);
+        assert_eq!(riggings[1].influences().collect::<Vec<_>>(), vec![(1, 1.0)]);
+        assert_eq!(
+            riggings[2].influences().collect::<Vec<_>>(),
+            vec![(0, 0.5), (1, 0.5)]
+        );
+        let (names, matrices) = skin_buffers(&mut ism2);
+        assert_eq!(names, vec!["root", "child"]);
+        assert_eq!(matrices.len(), 32);
+        assert!(matrices[..16].iter().all(|&v| v == 0.0));
+        assert!(matrices[16..].iter().all(|&v| v == 2.0));
+    }
+
+    #[test]
+    fn remove_root() {
+        let mut ism2 = model();
+        match ism2.remove_joint(1) {
+            Err(SkeletonEditError::UnboundInfluences(name)) => assert_eq!(name, "root"),
+            _ => panic!("Expected unbound influences"),
+        }
+        match ism2.remove_joint(3) {
+            Err(SkeletonEditError::NoSuchJoint(3)) => {}
+            _ => panic!("Expected a missing joint"),
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use crate::model_data::{MeshSubSection, ModelData, SubSection, Unnamed0A, VerticesDataBuffer};
+use crate::skeleton::Skeleton;
+use std::collections::HashSet;
+
+/// How far from 1 the weights of a vertex can sum up to
+pub const WEIGHT_SUM_TOLERANCE: f32 = 1e-3;
+
+/// Face index pointing past the end of the geometry vertices of its primitive
+#[derive(Clone, Debug)]
+pub struct IndexOutOfRange {
+    pub group: usize,
+    pub primitive: usize,
+    pub face: usize,
+    pub index: u32,
+    pub nb_vertices: usize,
+}
+
+/// Triangle of a primitive of a group, see `Unnamed0A::primitives` and `Faces::triangles`
+#[derive(Clone, Debug)]
+pub struct FaceIssue {
+    pub group: usize,
+    pub primitive: usize,
+    pub face: usize,
+}
+
+/// Vertex of a vertex buffer, `sub_section` is the index of the buffer in the sub-sections of the group
+#[derive(Clone, Debug)]
+pub struct VertexIssue {
+    pub group: usize,
+    pub sub_section: usize,
+    pub vertex: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct WeightSum {
+    pub group: usize,
+    pub sub_section: usize,
+    pub vertex: usize,
+    pub sum: f32,
+}
+
+/// Rigging influence whose joint is not the in-vertex ID of any joint of the skeleton
+#[derive(Clone, Debug)]
+pub struct UnknownJoint {
+    pub group: usize,
+    pub sub_section: usize,
+    pub vertex: usize,
+    pub in_vertex_id: u16,
+}
+
+/// Everything wrong with the geometry of a file
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    pub out_of_range_indices: Vec<IndexOutOfRange>,
+    /// Faces using the same vertex twice or with no area
+    pub degenerate_faces: Vec<FaceIssue>,
+    /// Faces using the same vertices as an earlier face of the same primitive
+    pub duplicate_faces: Vec<FaceIssue>,
+    /// Vertices with a NaN or infinite coordinate
+    pub non_finite_positions: Vec<VertexIssue>,
+    pub bad_weight_sums: Vec<WeightSum>,
+    pub unknown_joints: Vec<UnknownJoint>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.out_of_range_indices.is_empty()
+            && self.degenerate_faces.is_empty()
+            && self.duplicate_faces.is_empty()
+            && self.non_finite_positions.is_empty()
+            && self.bad_weight_sums.is_empty()
+            && self.unknown_joints.is_empty()
+    }
+
+    /// Adds the issues of another report to this one
+    pub fn append(&mut self, other: &mut ValidationReport) {
+        self.out_of_range_indices
+            .append(&mut other.out_of_range_indices);
+        self.degenerate_faces.append(&mut other.degenerate_faces);
+        self.duplicate_faces.append(&mut other.duplicate_faces);
+        self.non_finite_positions
+            .append(&mut other.non_finite_positions);
+        self.bad_weight_sums.append(&mut other.bad_weight_sums);
+        self.unknown_joints.append(&mut other.unknown_joints);
+    }
+}
+
+impl ModelData {
+    /// Checks faces, positions and rigging.
+    /// Without a skeleton, every rigging influence is reported as an unknown joint.
+    pub fn validate(&self, skeleton: Option<&Skeleton>) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let known_joints: HashSet<u32> = match skeleton {
+            Some(s) => s.joints().iter().map(|j| j.in_vertex_id).collect(),
+            None => HashSet::new(),
+        };
+        for (group, zero_a) in self.groups.iter().enumerate() {
+            validate_group(group, zero_a, &known_joints, &mut report);
+        }
+        report
+    }
+}
+
+fn validate_group(
+    group: usize,
+    zero_a: &Unnamed0A,
+    known_joints: &HashSet<u32>,
+    report: &mut ValidationReport,
+) {
+    for (sub_section, s) in zero_a.sub_sections.iter().enumerate() {
+        let vertices = match s {
+            SubSection::Vertices(v) => v,
+            _ => continue,
+        };
+        match vertices.buffer {
+            VerticesDataBuffer::Geometry(ref g) => {
+                for (vertex, v) in g.vertices.iter().enumerate() {
+                    let p = &v.position_coordinates;
+                    if !(p.x.is_finite() && p.y.is_finite() && p.z.is_finite()) {
+                        report.non_finite_positions.push(VertexIssue {
+                            group,
+                            sub_section,
+                            vertex,
+                        });
+                    }
+                }
+            }
+            VerticesDataBuffer::Rigging(ref r) => {
+                for (vertex, v) in r.vertices.iter().enumerate() {
+                    let sum = v.influences().map(|(_, w)| w).sum::<f32>();
+                    if (sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+                        report.bad_weight_sums.push(WeightSum {
+                            group,
+                            sub_section,
+                            vertex,
+                            sum,
+                        });
+                    }
+                    for (in_vertex_id, _) in v.influences() {
+                        if !known_joints.contains(&u32::from(in_vertex_id)) {
+                            report.unknown_joints.push(UnknownJoint {
+                                group,
+                                sub_section,
+                                vertex,
+                                in_vertex_id,
+                            });
+                        }
+                    }
+                }
+            }
+            VerticesDataBuffer::Unknown08(_) => {}
+        }
+    }
+    for (primitive, p) in zero_a.primitives().iter().enumerate() {
+        let geometry = match p.geometry.map(|v| &v.buffer) {
+            Some(VerticesDataBuffer::Geometry(g)) => &g.vertices[..],
+            _ => &[],
+        };
+        let mut seen = HashSet::new();
+        let faces = p.mesh.sub_sections.iter().filter_map(|s| match s {
+            MeshSubSection::Faces(f) => Some(f),
+            _ => None,
+        });
+        for (face, f) in faces.flat_map(|f| f.triangles()).enumerate() {
+            let (a, b, c) = f.points;
+            let mut in_range = true;
+            for &index in &[a, b, c] {
+                if index as usize >= geometry.len() {
+                    in_range = false;
+                    report.out_of_range_indices.push(IndexOutOfRange {
+                        group,
+                        primitive,
+                        face,
+                        index,
+                        nb_vertices: geometry.len(),
+                    });
+                }
+            }
+            let degenerate = a == b
+                || b == c
+                || a == c
+                || (in_range && {
+                    let position = |i: u32| {
+                        let p = &geometry[i as usize].position_coordinates;
+                        [p.x, p.y, p.z]
+                    };
+                    has_no_area(position(a), position(b), position(c))
+                });
+            if degenerate {
+                report.degenerate_faces.push(FaceIssue {
+                    group,
+                    primitive,
+                    face,
+                });
+            }
+            let mut key = [a, b, c];
+            key.sort_unstable();
+            if !seen.insert(key) {
+                report.duplicate_faces.push(FaceIssue {
+                    group,
+                    primitive,
+                    face,
+                });
+            }
+        }
+    }
+}
+
+/// Whether the area of a triangle is negligible next to the size of its longest edge
+fn has_no_area(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> bool {
+    let sub = |p: [f32; 3], q: [f32; 3]| [p[0] - q[0], p[1] - q[1], p[2] - q[2]];
+    let length2 = |v: [f32; 3]| v[0] * v[0] + v[1] * v[1] + v[2] * v[2];
+    let u = sub(b, a);
+    let v = sub(c, a);
+    let cross = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let longest = length2(u).max(length2(v)).max(length2(sub(c, b)));
+    length2(cross).sqrt() <= f32::EPSILON * longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::joint_definition::{Joint, JointDefinition, JointDefinitionSubSection};
+    use crate::math::RotationOrder;
+    use crate::model_data::{
+        Faces, FrenetFrame, IndexFormat, Mesh, Topology, Vector2D, Vector3D, VertexGeometry,
+        VertexRigging, Vertices, VerticesGeometryBuffer, VerticesRiggingBuffer,
+    };
+    use half::f16;
+
+    fn geometry(x: f32, y: f32) -> VertexGeometry {
+        let zero = f16::from_f32(0.0);
+        VertexGeometry {
+            position_coordinates: Vector3D { x, y, z: 0.0 },
+            texture_coordinates: Vector2D { u: zero, v: zero },
+            extra_texture_coordinates: Vec::new(),
+            frenet_frame: FrenetFrame {
+                normal: Vector3D {
+                    x: zero,
+                    y: zero,
+                    z: f16::from_f32(1.0),
+                },
+                tangent: Vector3D {
+                    x: f16::from_f32(1.0),
+                    y: zero,
+                    z: zero,
+                },
+            },
+            color: None,
+        }
+    }
+
+    fn rigging(joint: u16, weight: f32) -> VertexRigging {
+        let mut rigging = VertexRigging {
+            joints: [0; 8],
+            weights: [0.0; 8],
+            nb_influences: 4,
+        };
+        rigging.joints[0] = joint;
+        rigging.weights[0] = weight;
+        rigging
+    }
+
+    fn vertices(buffer: VerticesDataBuffer) -> SubSection {
+        SubSection::Vertices(Vertices {
+            nb_vertices: 5,
+            attributes: Vec::new(),
+            buffer,
+        })
+    }
+
+    /// Every issue appears exactly once
+    fn model_data() -> ModelData {
+        let geometry = VerticesGeometryBuffer {
+            vertices: vec![
+                geometry(0.0, 0.0),
+                geometry(1.0, 0.0),
+                geometry(0.0, 1.0),
+                geometry(f32::NAN, 0.0),
+                geometry(2.0, 0.0),
+            ],
+        };
+        let rigging = VerticesRiggingBuffer {
+            vertices: vec![
+                rigging(0, 1.0),
+                rigging(0, 0.5),
+                rigging(7, 1.0),
+                rigging(0, 1.0),
+                rigging(0, 1.0),
+            ],
+        };
+        #[rustfmt::skip]
+        let indices = vec![
+            0, 1, 2,
+            // Same vertices as the first face
+            2, 1, 0,
+            // On a line
+            0, 1, 4,
+            0, 1, 9,
+        ];
+        let mesh = Mesh {
+            unknown: [0; 3],
+            nb_faces: 4,
+            sub_sections: vec![MeshSubSection::Faces(Faces {
+                header: [0x02, 0x04, 0],
+                index_format: IndexFormat::U16,
+                topology: Topology::TriangleList,
+                indices,
+            })],
+        };
+        ModelData {
+            groups: vec![Unnamed0A {
+                sub_sections: vec![
+                    vertices(VerticesDataBuffer::Geometry(geometry)),
+                    vertices(VerticesDataBuffer::Rigging(rigging)),
+                    SubSection::Mesh(mesh),
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn every_issue() {
+        let joint_definition = JointDefinition {
+            sub_sections: vec![JointDefinitionSubSection::Joint(Joint {
+                name: "root".to_string(),
+                parent_index: None,
+                sub_sections: Vec::new(),
+                in_vertex_id: 0,
+                rotation_order: RotationOrder::Xyz,
+            })],
+        };
+        let skeleton = Skeleton::new(&joint_definition);
+        let report = model_data().validate(Some(&skeleton));
+        assert!(!report.is_valid());
+
+        assert_eq!(report.out_of_range_indices.len(), 1);
+        let out_of_range = &report.out_of_range_indices[0];
+        assert_eq!(
+            (
+                out_of_range.face,
+                out_of_range.index,
+                out_of_range.nb_vertices
+            ),
+            (3, 9, 5)
+        );
+        assert_eq!(report.duplicate_faces.len(), 1);
+        assert_eq!(report.duplicate_faces[0].face, 1);
+        assert_eq!(report.degenerate_faces.len(), 1);
+        assert_eq!(report.degenerate_faces[0].face, 2);
+
+        assert_eq!(report.non_finite_positions.len(), 1);
+        let non_finite = &report.non_finite_positions[0];
+        assert_eq!((non_finite.sub_section, non_finite.vertex), (0, 3));
+        assert_eq!(report.bad_weight_sums.len(), 1);
+        let weight_sum = &report.bad_weight_sums[0];
+        assert_eq!((weight_sum.sub_section, weight_sum.vertex), (1, 1));
+        assert_eq!(weight_sum.sum, 0.5);
+        assert_eq!(report.unknown_joints.len(), 1);
+        let unknown_joint = &report.unknown_joints[0];
+        assert_eq!((unknown_joint.vertex, unknown_joint.in_vertex_id), (2, 7));
+    }
+
+    #[test]
+    fn without_skeleton() {
+        let report = model_data().validate(None);
+        assert_eq!(report.unknown_joints.len(), 5);
+    }
+}